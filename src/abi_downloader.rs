@@ -1,38 +1,316 @@
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::Duration;
 use configparser::ini::Ini;
-use foundry_block_explorers::Client;
-use alloy_json_abi::{JsonAbi, Function, Event};
+use alloy_json_abi::{JsonAbi, Function, Event, EventParam, Error as AbiError, InternalType, Param, StateMutability};
 use alloy_chains::Chain;
 use alloy_primitives::Address;
 use polars::prelude::*;
 use anyhow::{anyhow, Result};
-use tokio::time;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use log::{info, warn};
+use serde::{Deserialize, Serialize};
 use tiny_keccak::{Hasher, Keccak};
+use crate::cache::AbiCache;
+use crate::local::LocalAbi;
+use crate::diamond::{is_diamond, DiamondResolver, Facet};
+use crate::proxy::ProxyResolver;
+use crate::report::{selector_violations, RunReport, SelectorViolation};
+use crate::retry::{retry, RetryError, RetryPolicy};
+use crate::source::{AbiSource, ContractSource, FetchedAbi};
+use crate::state::{EntryStatus, RunState, StateEntry};
 
-const RATE_LIMIT: Duration = Duration::from_millis(333);
-
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AbiRecord {
+    pub chain_id: u64,
     pub record_type: String,
     pub contract_address: String,
+    /// From the address file label, or the verified source when downloaded.
+    pub contract_name: Option<String>,
     pub name: String,
+    /// Position among the records of the same type and name in the ABI, 0 when
+    /// the name is not overloaded.
+    pub overload_index: u32,
+    /// Signature with parameter names, e.g. `transfer(address to, uint256 amount)`.
+    pub display_name: String,
     pub signature: String,
-    pub selector: String,
+    /// 4-byte selector for functions and errors, topic0 for events. Null for
+    /// anonymous events, whose topic0 is their first indexed argument.
+    pub selector: Option<String>,
+    /// Functions only.
+    pub state_mutability: Option<String>,
+    pub input_names: Vec<String>,
+    /// Canonical types, with tuples expanded as in the signature.
+    pub input_types: Vec<String>,
+    /// Functions only.
+    pub output_names: Vec<String>,
+    /// Functions only.
+    pub output_types: Vec<String>,
+    /// Events only, one flag per input.
+    pub indexed: Vec<bool>,
+    /// Events only.
+    pub anonymous: Option<bool>,
+    /// Events only, the number of topics taken by indexed inputs (excluding
+    /// the signature topic).
+    pub indexed_topics: Option<u32>,
+    /// Input parameter tree, flattened depth-first; see [`ParamNode`].
+    pub input_tree: Vec<ParamNode>,
+    /// Functions only.
+    pub output_tree: Vec<ParamNode>,
+    /// Inputs exactly as in the ABI JSON, including struct components.
+    pub inputs_json: String,
+    /// Functions only.
+    pub outputs_json: Option<String>,
+    /// Set on an implementation's records to the proxy that delegates to it.
+    pub proxy_address: Option<String>,
+    /// Set on a proxy's records to the implementation it delegates to.
+    pub implementation_address: Option<String>,
+    /// Set on records of a diamond's facets, which are attributed to the diamond.
+    pub facet_address: Option<String>,
+}
+
+/// One parameter of a possibly nested parameter tree. `path` holds the indices
+/// from the top-level parameter down, e.g. `1.0` is the first component of the
+/// second parameter, so the tree can be rebuilt from the flattened list.
+#[derive(Debug, Clone, Default)]
+pub struct ParamNode {
+    pub path: String,
+    pub name: String,
+    pub ty: String,
+    pub internal_type: Option<String>,
+}
+
+/// Entry points of a contract that are not selector-addressed: its constructor,
+/// and whether it accepts plain ETH transfers or arbitrary calldata.
+#[derive(Debug)]
+pub struct ContractMetadata {
+    pub chain_id: u64,
+    pub contract_address: String,
+    pub contract_name: Option<String>,
+    pub constructor_inputs: Vec<String>,
+    /// `None` when the ABI has no constructor.
+    pub constructor_payable: Option<bool>,
+    pub has_fallback: bool,
+    pub fallback_mutability: Option<String>,
+    pub has_receive: bool,
+    pub receive_mutability: Option<String>,
+}
+
+/// Verified source metadata of a contract, written to `contracts.parquet`.
+#[derive(Debug, Default)]
+pub struct ContractInfo {
+    pub chain_id: u64,
+    pub contract_address: String,
+    /// Set on the implementation of a resolved proxy.
+    pub proxy_address: Option<String>,
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub optimization_used: bool,
+    pub runs: u64,
+    pub evm_version: String,
+    pub license: String,
+    pub libraries: String,
+    /// Directory the source files were written to.
+    pub source_dir: String,
+    /// Paths of the source files, relative to `source_dir`.
+    pub source_files: Vec<String>,
+}
+
+/// Records of one contract, split by the table they are written to.
+#[derive(Debug, Default)]
+pub struct ContractRecords {
+    pub functions: Vec<AbiRecord>,
+    pub events: Vec<AbiRecord>,
+    pub errors: Vec<AbiRecord>,
+    pub metadata: Vec<ContractMetadata>,
+    /// Only filled when downloading with source.
+    pub contracts: Vec<ContractInfo>,
+    /// Columns from the address file added to every table of the contract.
+    pub labels: Vec<(String, Option<String>)>,
+    /// Selectors that are not unique within the contract's ABI.
+    pub violations: Vec<SelectorViolation>,
+}
+
+impl ContractRecords {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut AbiRecord> {
+        self.functions.iter_mut().chain(self.events.iter_mut()).chain(self.errors.iter_mut())
+    }
+
+    pub fn set_contract_name(&mut self, contract_name: Option<String>) {
+        for record in self.iter_mut() {
+            record.contract_name = contract_name.clone();
+        }
+        for metadata in self.metadata.iter_mut() {
+            metadata.contract_name = contract_name.clone();
+        }
+    }
+
+    pub fn extend(&mut self, other: ContractRecords) {
+        self.functions.extend(other.functions);
+        self.events.extend(other.events);
+        self.errors.extend(other.errors);
+        self.metadata.extend(other.metadata);
+        self.contracts.extend(other.contracts);
+        self.violations.extend(other.violations);
+    }
+}
+
+/// Per-contract table files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractFiles {
+    pub functions: PathBuf,
+    pub events: PathBuf,
+    pub errors: PathBuf,
+    pub metadata: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contracts: Option<PathBuf>,
+}
+
+impl ContractFiles {
+    pub fn exist(&self) -> bool {
+        [&self.functions, &self.events, &self.errors, &self.metadata].iter().all(|f| f.exists())
+            && self.contracts.iter().all(|f| f.exists())
+    }
 }
 
-pub fn write_parquet(records: &[AbiRecord], filename: &Path) -> Result<()> {
+/// Table files of every contract in a run, grouped by table.
+#[derive(Debug, Clone, Default)]
+pub struct TableFiles {
+    pub functions: Vec<PathBuf>,
+    pub events: Vec<PathBuf>,
+    pub errors: Vec<PathBuf>,
+    pub metadata: Vec<PathBuf>,
+    /// Only contracts whose source was downloaded.
+    pub contracts: Vec<PathBuf>,
+}
+
+impl TableFiles {
+    pub fn push(&mut self, files: ContractFiles) {
+        self.functions.push(files.functions);
+        self.events.push(files.events);
+        self.errors.push(files.errors);
+        self.metadata.push(files.metadata);
+        self.contracts.extend(files.contracts);
+    }
+
+    /// Pairs each table name with its files, e.g. to write `all_<name>.parquet`.
+    pub fn tables(&self) -> [(&'static str, &[PathBuf]); 4] {
+        [
+            ("functions", self.functions.as_slice()),
+            ("events", self.events.as_slice()),
+            ("errors", self.errors.as_slice()),
+            ("metadata", self.metadata.as_slice()),
+        ]
+    }
+}
+
+pub fn write_parquet(records: &[AbiRecord], labels: &[(String, Option<String>)], filename: &Path) -> Result<()> {
     let mut df = DataFrame::new(vec![
+        Series::new("chain_id", records.iter().map(|r| r.chain_id).collect::<Vec<_>>()),
         Series::new("record_type", records.iter().map(|r| r.record_type.clone()).collect::<Vec<_>>()),
         Series::new("contract_address", records.iter().map(|r| r.contract_address.clone()).collect::<Vec<_>>()),
+        Series::new("contract_name", records.iter().map(|r| r.contract_name.clone()).collect::<Vec<_>>()),
         Series::new("name", records.iter().map(|r| r.name.clone()).collect::<Vec<_>>()),
+        Series::new("overload_index", records.iter().map(|r| r.overload_index).collect::<Vec<_>>()),
+        Series::new("display_name", records.iter().map(|r| r.display_name.clone()).collect::<Vec<_>>()),
         Series::new("signature", records.iter().map(|r| r.signature.clone()).collect::<Vec<_>>()),
         Series::new("selector", records.iter().map(|r| r.selector.clone()).collect::<Vec<_>>()),
+        Series::new("state_mutability", records.iter().map(|r| r.state_mutability.clone()).collect::<Vec<_>>()),
+        list_column("input_names", records.iter().map(|r| Series::new("", &r.input_names)).collect(), DataType::String)?,
+        list_column("input_types", records.iter().map(|r| Series::new("", &r.input_types)).collect(), DataType::String)?,
+        list_column("output_names", records.iter().map(|r| Series::new("", &r.output_names)).collect(), DataType::String)?,
+        list_column("output_types", records.iter().map(|r| Series::new("", &r.output_types)).collect(), DataType::String)?,
+        list_column("indexed", records.iter().map(|r| Series::new("", &r.indexed)).collect(), DataType::Boolean)?,
+        Series::new("anonymous", records.iter().map(|r| r.anonymous).collect::<Vec<_>>()),
+        Series::new("indexed_topics", records.iter().map(|r| r.indexed_topics).collect::<Vec<_>>()),
+        param_tree_column("input_tree", records.iter().map(|r| r.input_tree.as_slice()))?,
+        param_tree_column("output_tree", records.iter().map(|r| r.output_tree.as_slice()))?,
+        Series::new("inputs_json", records.iter().map(|r| r.inputs_json.clone()).collect::<Vec<_>>()),
+        Series::new("outputs_json", records.iter().map(|r| r.outputs_json.clone()).collect::<Vec<_>>()),
+        Series::new("proxy_address", records.iter().map(|r| r.proxy_address.clone()).collect::<Vec<_>>()),
+        Series::new("implementation_address", records.iter().map(|r| r.implementation_address.clone()).collect::<Vec<_>>()),
+        Series::new("facet_address", records.iter().map(|r| r.facet_address.clone()).collect::<Vec<_>>()),
+    ])?;
+    add_label_columns(&mut df, labels)?;
+
+    let mut file = File::create(filename)?;
+    ParquetWriter::new(&mut file).finish(&mut df)?;
+    Ok(())
+}
+
+/// Adds a column per label, repeating the contract's value on every row.
+fn add_label_columns(df: &mut DataFrame, labels: &[(String, Option<String>)]) -> Result<()> {
+    let height = df.height();
+    for (name, value) in labels {
+        df.with_column(Series::new(name, vec![value.clone(); height]))?;
+    }
+    Ok(())
+}
+
+// the inner type is set explicitly so that tables without rows still share the
+// schema of the others and can be concatenated
+pub(crate) fn list_column(name: &str, values: Vec<Series>, inner: DataType) -> Result<Series> {
+    Ok(Series::new(name, values).cast(&DataType::List(Box::new(inner)))?)
+}
+
+fn param_tree_column<'a>(name: &str, trees: impl Iterator<Item = &'a [ParamNode]>) -> Result<Series> {
+    let node_type = DataType::Struct(vec![
+        Field::new("path", DataType::String),
+        Field::new("name", DataType::String),
+        Field::new("type", DataType::String),
+        Field::new("internal_type", DataType::String),
+    ]);
+    let values = trees
+        .map(|nodes| {
+            let fields = [
+                Series::new("path", nodes.iter().map(|n| n.path.clone()).collect::<Vec<_>>()),
+                Series::new("name", nodes.iter().map(|n| n.name.clone()).collect::<Vec<_>>()),
+                Series::new("type", nodes.iter().map(|n| n.ty.clone()).collect::<Vec<_>>()),
+                Series::new("internal_type", nodes.iter().map(|n| n.internal_type.clone()).collect::<Vec<_>>()),
+            ];
+            Ok(StructChunked::new("", &fields)?.into_series())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    list_column(name, values, node_type)
+}
+
+pub fn write_metadata_parquet(records: &[ContractMetadata], labels: &[(String, Option<String>)], filename: &Path) -> Result<()> {
+    let mut df = DataFrame::new(vec![
+        Series::new("chain_id", records.iter().map(|r| r.chain_id).collect::<Vec<_>>()),
+        Series::new("contract_address", records.iter().map(|r| r.contract_address.clone()).collect::<Vec<_>>()),
+        Series::new("contract_name", records.iter().map(|r| r.contract_name.clone()).collect::<Vec<_>>()),
+        list_column("constructor_inputs", records.iter().map(|r| Series::new("", &r.constructor_inputs)).collect(), DataType::String)?,
+        Series::new("constructor_payable", records.iter().map(|r| r.constructor_payable).collect::<Vec<_>>()),
+        Series::new("has_fallback", records.iter().map(|r| r.has_fallback).collect::<Vec<_>>()),
+        Series::new("fallback_mutability", records.iter().map(|r| r.fallback_mutability.clone()).collect::<Vec<_>>()),
+        Series::new("has_receive", records.iter().map(|r| r.has_receive).collect::<Vec<_>>()),
+        Series::new("receive_mutability", records.iter().map(|r| r.receive_mutability.clone()).collect::<Vec<_>>()),
+    ])?;
+    add_label_columns(&mut df, labels)?;
+
+    let mut file = File::create(filename)?;
+    ParquetWriter::new(&mut file).finish(&mut df)?;
+    Ok(())
+}
+
+pub fn write_contracts_parquet(records: &[ContractInfo], labels: &[(String, Option<String>)], filename: &Path) -> Result<()> {
+    let mut df = DataFrame::new(vec![
+        Series::new("chain_id", records.iter().map(|r| r.chain_id).collect::<Vec<_>>()),
+        Series::new("contract_address", records.iter().map(|r| r.contract_address.clone()).collect::<Vec<_>>()),
+        Series::new("proxy_address", records.iter().map(|r| r.proxy_address.clone()).collect::<Vec<_>>()),
+        Series::new("contract_name", records.iter().map(|r| r.contract_name.clone()).collect::<Vec<_>>()),
+        Series::new("compiler_version", records.iter().map(|r| r.compiler_version.clone()).collect::<Vec<_>>()),
+        Series::new("optimization_used", records.iter().map(|r| r.optimization_used).collect::<Vec<_>>()),
+        Series::new("runs", records.iter().map(|r| r.runs).collect::<Vec<_>>()),
+        Series::new("evm_version", records.iter().map(|r| r.evm_version.clone()).collect::<Vec<_>>()),
+        Series::new("license", records.iter().map(|r| r.license.clone()).collect::<Vec<_>>()),
+        Series::new("libraries", records.iter().map(|r| r.libraries.clone()).collect::<Vec<_>>()),
+        Series::new("source_dir", records.iter().map(|r| r.source_dir.clone()).collect::<Vec<_>>()),
+        list_column("source_files", records.iter().map(|r| Series::new("", &r.source_files)).collect(), DataType::String)?,
     ])?;
+    add_label_columns(&mut df, labels)?;
 
     let mut file = File::create(filename)?;
     ParquetWriter::new(&mut file).finish(&mut df)?;
@@ -64,93 +342,705 @@ pub fn read_api_key(config_path: &str) -> Result<String> {
 }
 
 
-pub fn create_etherscan_client(api_key: &str) -> Result<Client> {
-    Client::new(Chain::mainnet(), api_key)
-        .map_err(|e| anyhow!("Failed to create Etherscan client: {}", e))
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Maximum number of requests in flight at once, across all chains.
+    pub workers: usize,
+    pub retry: RetryPolicy,
+    /// Skip addresses that already succeeded or failed permanently in the previous run.
+    pub resume: bool,
+    pub cache: Option<AbiCache>,
+    /// Also fetch the implementation ABI of proxy contracts.
+    pub proxies: Option<ProxyResolver>,
+    /// Also fetch the facet ABIs of EIP-2535 diamonds.
+    pub diamonds: Option<DiamondResolver>,
+    /// Also fetch verified source code and compiler settings.
+    pub with_source: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions { workers: 4, retry: RetryPolicy::default(), resume: false, cache: None, proxies: None, diamonds: None, with_source: false }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AddressEntry {
+    pub chain: Chain,
+    pub address: Address,
+    /// Human-readable name given in the address file.
+    pub label: Option<String>,
+    /// Extra columns of a structured address file, as `(output column, value)`.
+    pub labels: Vec<(String, Option<String>)>,
+}
+
+pub fn format_address(address: &Address) -> String {
+    format!("0x{}", hex::encode(address.as_slice()))
 }
 
-pub fn read_addresses(filename: &str) -> Result<Vec<String>> {
+pub fn read_addresses(filename: &str, default_chain: Chain) -> Result<Vec<AddressEntry>> {
     let file = File::open(filename)?;
     let reader = BufReader::new(file);
-    Ok(reader.lines().filter_map(|line| line.ok()).collect())
+    let mut entries = Vec::new();
+    let mut first_line = true;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_address_line(line, default_chain);
+        // a header row such as `chain,address` is allowed before the first address,
+        // but a first row whose label merely mentions "address" is kept
+        if std::mem::take(&mut first_line) && entry.is_err() && line.to_lowercase().contains("address") {
+            continue;
+        }
+        entries.push(entry.map_err(|e| anyhow!("{}:{}: {}", filename, index + 1, e))?);
+    }
+    Ok(entries)
 }
 
-pub async fn download_abis(client: &Client, addresses: &[String], output_dir: &PathBuf) 
--> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
-    let functions_dir = output_dir.join("functions");
-    let events_dir = output_dir.join("events");
-
-    let mut function_files = Vec::new();
-    let mut event_files = Vec::new();
+/// Parses `address`, `chain:address` or `chain,address` into an entry, each
+/// optionally followed by `,label`.
+pub fn parse_address_line(line: &str, default_chain: Chain) -> Result<AddressEntry> {
+    let mut fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+    if let Some((chain, address)) = fields[0].split_once(':') {
+        fields.splice(0..1, [chain.trim(), address.trim()]);
+    }
+    // a bare address takes the default chain
+    if Address::from_str(fields[0]).is_ok() {
+        fields.insert(0, "");
+    }
+    let (chain, address, label) = match fields.as_slice() {
+        [chain, address] => (*chain, *address, None),
+        [chain, address, label] => (*chain, *address, Some(*label).filter(|label| !label.is_empty())),
+        _ => return Err(anyhow!("expected address, chain:address or chain,address, optionally followed by a label")),
+    };
+    let chain = match chain {
+        "" => default_chain,
+        chain => Chain::from_str(chain).map_err(|_| anyhow!("invalid chain '{}'", chain))?,
+    };
+    let address = Address::from_str(address)
+        .map_err(|e| anyhow!("invalid address '{}': {}", address, e))?;
+    Ok(AddressEntry { chain, address, label: label.map(String::from), labels: Vec::new() })
+}
 
-    std::fs::create_dir_all(&functions_dir)
+pub fn create_chain_dirs(output_dir: &Path, chain_id: u64) -> Result<()> {
+    let chain_dir = output_dir.join(chain_id.to_string());
+    std::fs::create_dir_all(chain_dir.join("functions"))
     .map_err(|e| anyhow!("failed to create functions output dir. {:?}", e))?;
-    std::fs::create_dir_all(&events_dir)
+    std::fs::create_dir_all(chain_dir.join("events"))
     .map_err(|e| anyhow!("failed to create events output dir. {:?}", e))?;
+    std::fs::create_dir_all(chain_dir.join("errors"))
+    .map_err(|e| anyhow!("failed to create errors output dir. {:?}", e))?;
+    std::fs::create_dir_all(chain_dir.join("metadata"))
+    .map_err(|e| anyhow!("failed to create metadata output dir. {:?}", e))?;
+    std::fs::create_dir_all(chain_dir.join("contracts"))
+    .map_err(|e| anyhow!("failed to create contracts output dir. {:?}", e))?;
+    Ok(())
+}
+
+pub async fn download_abis<S: AbiSource + ?Sized>(source: &S, entries: &[AddressEntry], output_dir: &PathBuf, options: &DownloadOptions) 
+-> Result<TableFiles> {
+    let chain_ids = entries.iter().map(|entry| entry.chain.id()).collect::<HashSet<_>>();
+    for chain_id in chain_ids {
+        create_chain_dirs(output_dir, chain_id)?;
+    }
+
+    let state = RunState::open(output_dir, options.resume)?;
+    let mut outstanding = Vec::new();
+    let mut queued = HashSet::new();
+    for entry in entries {
+        let address_str = format_address(&entry.address);
+        // an address listed more than once is downloaded once
+        if !queued.insert((entry.chain.id(), address_str.clone())) {
+            continue;
+        }
+        if state.is_done(entry.chain.id(), &address_str) {
+            continue;
+        }
+        state.record(StateEntry::pending(entry.chain.id(), &address_str))?;
+        outstanding.push(entry);
+    }
+    if options.resume {
+        info!("Resuming run, {} of {} addresses outstanding", outstanding.len(), queued.len());
+    }
+
+    let total = outstanding.len();
+    stream::iter(outstanding.into_iter().enumerate())
+        .map(|(index, entry)| download_abi(source, &state, entry, index, total, output_dir, options))
+        .buffer_unordered(options.workers.max(1))
+        // stops at the first fatal error instead of draining the whole list
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // collect outputs from this run and any resumed ones, in address file order
+    let mut table_files = TableFiles::default();
+    let mut report = RunReport::default();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        let Some(state_entry) = state.get(entry.chain.id(), &format_address(&entry.address)) else {
+            continue;
+        };
+        if !seen.insert((state_entry.chain_id, state_entry.address.clone())) {
+            continue;
+        }
+        match state_entry.status {
+            EntryStatus::Succeeded => report.succeeded += 1,
+            EntryStatus::Failed => report.failed += 1,
+            EntryStatus::Pending => report.pending += 1,
+        }
+        if state_entry.status != EntryStatus::Succeeded {
+            continue;
+        }
+        report.selector_violations.extend(state_entry.violations);
+        if let Some(files) = state_entry.files {
+            table_files.push(files);
+        }
+    }
+    report.write(output_dir)?;
+    Ok(table_files)
+}
+
+async fn download_abi<S: AbiSource + ?Sized>(source: &S, state: &RunState, entry: &AddressEntry, index: usize, total: usize, output_dir: &Path, options: &DownloadOptions)
+-> Result<()> {
+    let chain_id = entry.chain.id();
+    let address_str = format_address(&entry.address);
+
+    info!("Downloading ABI for address {} on {} ({}/{})", address_str, entry.chain, index + 1, total);
+    let result = fetch_abi(source, options, entry.chain, entry.address).await;
+    let mut state_entry = StateEntry::pending(chain_id, &address_str);
+    match result {
+        Ok(Some(FetchedAbi { abi: abi_json, source: fetched_source })) => {
+            let mut records = process_contract(chain_id, &address_str, &abi_json)?;
+            // the etherscan ABI lookup already returns the source metadata; other sources
+            // need one more request, made only when proxies or sources are asked for
+            let contract_source = match fetched_source {
+                Some(contract_source) => Some(contract_source),
+                None if options.proxies.is_some() || options.with_source => {
+                    fetch_contract_source(source, options, entry.chain, entry.address).await?
+                }
+                None => None,
+            };
+            if options.with_source {
+                records.contracts.extend(contract_info(entry.chain, entry.address, contract_source.as_ref(), output_dir)?);
+            }
+            let contract_name = entry.label.clone().or_else(|| source_contract_name(contract_source.as_ref()));
+            records.labels = entry.labels.clone();
+            records.set_contract_name(contract_name.clone());
+            if let Some(resolver) = &options.proxies {
+                let recorded = contract_source.as_ref().and_then(|s| s.implementation);
+                if let Some((implementation, implementation_abi)) = fetch_implementation(source, resolver, entry, recorded, options).await? {
+                    let implementation_str = format_address(&implementation);
+                    let mut implementation_records = process_contract(chain_id, &implementation_str, &implementation_abi.abi)?;
+                    let implementation_source = match implementation_abi.source {
+                        Some(contract_source) => Some(contract_source),
+                        None if options.with_source => fetch_contract_source(source, options, entry.chain, implementation).await?,
+                        None => None,
+                    };
+                    if options.with_source {
+                        implementation_records.contracts.extend(contract_info(entry.chain, implementation, implementation_source.as_ref(), output_dir)?);
+                    }
+                    implementation_records.set_contract_name(source_contract_name(implementation_source.as_ref()));
+                    for record in records.iter_mut() {
+                        record.implementation_address = Some(implementation_str.clone());
+                    }
+                    for record in implementation_records.iter_mut() {
+                        record.proxy_address = Some(address_str.clone());
+                    }
+                    for contract in implementation_records.contracts.iter_mut() {
+                        contract.proxy_address = Some(address_str.clone());
+                    }
+                    records.extend(implementation_records);
+                }
+            }
+            if let Some(resolver) = &options.diamonds {
+                if is_diamond(&abi_json) {
+                    let facets = fetch_facets(source, resolver, entry, options).await?;
+                    // a selector routed to a facet is served by the facet's record, so a copy
+                    // in the diamond's own ABI would show up as a duplicate below
+                    let routed = facets.iter().flat_map(|(facet, _)| &facet.selectors).collect::<HashSet<_>>();
+                    records.functions.retain(|record| {
+                        record.contract_address != address_str || !record.selector.as_ref().is_some_and(|selector| routed.contains(selector))
+                    });
+                    for (facet, facet_abi) in facets {
+                        let mut facet_records = process_contract(chain_id, &address_str, &facet_abi)?;
+                        // only selectors the diamond routes to this facet are reachable through it
+                        facet_records.functions.retain(|record| record.selector.as_ref().is_some_and(|selector| facet.selectors.contains(selector)));
+                        // a facet's constructor and fallback are not the diamond's
+                        facet_records.metadata.clear();
+                        // events and errors are not routed, so one the diamond or another facet
+                        // already declares is the same one
+                        let declared = |records: &[AbiRecord], signature: &str| {
+                            records.iter().any(|record| record.contract_address == address_str && record.signature == signature)
+                        };
+                        facet_records.events.retain(|record| !declared(&records.events, &record.signature));
+                        facet_records.errors.retain(|record| !declared(&records.errors, &record.signature));
+                        for record in facet_records.iter_mut() {
+                            record.contract_name = contract_name.clone();
+                            record.facet_address = Some(format_address(&facet.address));
+                        }
+                        records.extend(facet_records);
+                    }
+                    // facet ABIs were checked before their unrouted selectors were dropped, and a
+                    // selector routed to two facets only shows in the combined records
+                    records.violations = selector_violations(&records);
+                }
+            }
+            warn_violations(&address_str, &records.violations);
+            let files = write_contract_tables(chain_id, &address_str, &records, output_dir)?;
+            state_entry.status = EntryStatus::Succeeded;
+            state_entry.files = Some(files);
+            state_entry.violations = records.violations;
+        },
+
+        Ok(None) => {
+            warn!("No verified ABI found for address {} on {} in {}", address_str, entry.chain, source.name());
+            state_entry.status = EntryStatus::Failed;
+            state_entry.error = Some("no verified ABI found".to_string());
+        }
+
+        Err(e) if e.class.is_fatal() => {
+            return Err(anyhow!("Aborting, failed to fetch ABI for address {} on {}: {}", address_str, entry.chain, e));
+        }
+
+        Err(e) => {
+            warn!("Failed to fetch ABI for address {} on {}: {}", address_str, entry.chain, e);
+            // transient failures stay pending so that a resumed run retries them
+            if !e.class.is_transient() {
+                state_entry.status = EntryStatus::Failed;
+            }
+            state_entry.error = Some(e.to_string());
+        }
+    }
+    state.record(state_entry)
+}
+
+/// Fetches an ABI through the cache, retrying transient source failures. The
+/// source metadata comes along when the source's lookup returned it.
+async fn fetch_abi<S: AbiSource + ?Sized>(source: &S, options: &DownloadOptions, chain: Chain, address: Address)
+-> std::result::Result<Option<FetchedAbi>, RetryError> {
+    let address_str = format_address(&address);
+    if let Some(cache) = &options.cache {
+        if let Some(abi_json) = cache.get(chain.id(), &address_str) {
+            info!("Using cached ABI for address {} on {}", address_str, chain);
+            let source = cache.get_source(chain.id(), &address_str).flatten();
+            return Ok(Some(FetchedAbi { abi: abi_json, source }));
+        }
+    }
+
+    let label = format!("{} on {}", address_str, chain);
+    let fetched = retry(&options.retry, &label, move || source.fetch_abi_with_source(chain, address)).await;
+    if let (Ok(Some(fetched)), Some(cache)) = (&fetched, &options.cache) {
+        if let Err(e) = cache.put(chain.id(), &address_str, &fetched.abi) {
+            warn!("Failed to cache ABI for address {} on {}: {}", address_str, chain, e);
+        }
+        if let Some(contract_source) = &fetched.source {
+            if let Err(e) = cache.put_source(chain.id(), &address_str, Some(contract_source)) {
+                warn!("Failed to cache source of {} on {}: {}", address_str, chain, e);
+            }
+        }
+    }
+    fetched
+}
+
+/// Fetches the source metadata of a contract through the cache. Failures other
+/// than fatal ones only cost what is read from it, not the contract's records.
+async fn fetch_contract_source<S: AbiSource + ?Sized>(source: &S, options: &DownloadOptions, chain: Chain, address: Address)
+-> Result<Option<ContractSource>> {
+    let address_str = format_address(&address);
+    if let Some(contract_source) = options.cache.as_ref().and_then(|cache| cache.get_source(chain.id(), &address_str)) {
+        return Ok(contract_source);
+    }
+
+    let label = format!("source of {} on {}", address_str, chain);
+    let contract_source = match retry(&options.retry, &label, move || source.fetch_source(chain, address)).await {
+        Ok(contract_source) => contract_source,
+        Err(e) if e.class.is_fatal() => {
+            return Err(anyhow!("Aborting, failed to fetch {}: {}", label, e));
+        }
+        Err(e) => {
+            warn!("Failed to fetch {}: {}", label, e);
+            return Ok(None);
+        }
+    };
+    if let Some(cache) = &options.cache {
+        if let Err(e) = cache.put_source(chain.id(), &address_str, contract_source.as_ref()) {
+            warn!("Failed to cache source of {} on {}: {}", address_str, chain, e);
+        }
+    }
+    Ok(contract_source)
+}
+
+fn source_contract_name(contract_source: Option<&ContractSource>) -> Option<String> {
+    contract_source.map(|s| s.contract_name.clone()).filter(|name| !name.is_empty())
+}
+
+/// Writes the files of a verified source under `<output_dir>/sources` and
+/// describes them, or warns when there is no source to write.
+fn contract_info(chain: Chain, address: Address, contract_source: Option<&ContractSource>, output_dir: &Path) -> Result<Option<ContractInfo>> {
+    let address_str = format_address(&address);
+    let Some(contract_source) = contract_source.filter(|s| !s.files.is_empty()) else {
+        warn!("No verified source found for address {} on {}", address_str, chain);
+        return Ok(None);
+    };
+
+    let source_dir = output_dir.join("sources").join(chain.id().to_string()).join(&address_str);
+    let source_files = write_source_files(&source_dir, contract_source)?;
+    Ok(Some(ContractInfo {
+        chain_id: chain.id(),
+        contract_address: address_str,
+        proxy_address: None,
+        contract_name: contract_source.contract_name.clone(),
+        compiler_version: contract_source.compiler_version.clone(),
+        optimization_used: contract_source.optimization_used,
+        runs: contract_source.runs,
+        evm_version: contract_source.evm_version.clone(),
+        license: contract_source.license.clone(),
+        libraries: contract_source.libraries.clone(),
+        source_dir: source_dir.to_string_lossy().into_owned(),
+        source_files,
+    }))
+}
+
+/// Writes each source file at its compilation path below `source_dir` and
+/// returns the paths written.
+pub fn write_source_files(source_dir: &Path, contract_source: &ContractSource) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    for (path, content) in &contract_source.files {
+        let Some(relative_path) = sanitize_source_path(path) else {
+            warn!("Skipping source file with unusable path '{}'", path);
+            continue;
+        };
+        let file_path = source_dir.join(&relative_path);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| anyhow!("failed to create source dir {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&file_path, content)
+            .map_err(|e| anyhow!("failed to write source file {}: {}", file_path.display(), e))?;
+        written.push(relative_path.to_string_lossy().into_owned());
+    }
+    Ok(written)
+}
 
-    let total = addresses.len();
-    for (index, address_str) in addresses.iter().enumerate() {
-        info!("Downloading ABI for address {} ({}/{})", address_str, index + 1, total);
-        std::io::stdout().flush()?;
-        let addr_rep = Address::from_str(&address_str)?;
-        match client.contract_abi(addr_rep).await {
-            Ok(abi_json) => {
-                let (functions, events) = process_contract(address_str, &abi_json)?;
-                let function_file = functions_dir.join(format!("{}_functions.parquet", address_str));
-                let event_file = events_dir.join(format!("{}_events.parquet", address_str));
-                write_parquet(&functions, &function_file)?;
-                write_parquet(&events, &event_file)?;
-                function_files.push(function_file);
-                event_files.push(event_file);
-            },
+/// Keeps only the normal components of a compilation path, so that paths such
+/// as `/src/A.sol` or `../../A.sol` from the explorer cannot escape the source dir.
+fn sanitize_source_path(path: &str) -> Option<PathBuf> {
+    let relative_path = path
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != "." && *component != "..")
+        .map(|component| component.replace(':', "_"))
+        .collect::<PathBuf>();
+    (relative_path.components().count() > 0).then_some(relative_path)
+}
 
+/// Resolves the implementation behind a proxy and fetches its ABI. Failures other
+/// than fatal ones only cost the implementation's records, not the proxy's.
+async fn fetch_implementation<S: AbiSource + ?Sized>(source: &S, resolver: &ProxyResolver, entry: &AddressEntry, recorded: Option<Address>, options: &DownloadOptions)
+-> Result<Option<(Address, FetchedAbi)>> {
+    let (chain, address) = (entry.chain, entry.address);
+    let address_str = format_address(&address);
+    let label = format!("implementation of {} on {}", address_str, chain);
+    let implementation = match retry(&options.retry, &label, move || resolver.resolve(chain, address, recorded)).await {
+        Ok(Some(implementation)) if implementation != address => implementation,
+        Ok(_) => return Ok(None),
+        Err(e) if e.class.is_fatal() => {
+            return Err(anyhow!("Aborting, failed to resolve {}: {}", label, e));
+        }
+        Err(e) => {
+            warn!("Failed to resolve {}: {}", label, e);
+            return Ok(None);
+        }
+    };
+
+    let implementation_str = format_address(&implementation);
+    info!("Address {} on {} is a proxy for {}", address_str, chain, implementation_str);
+    match fetch_abi(source, options, chain, implementation).await {
+        Ok(Some(fetched)) => Ok(Some((implementation, fetched))),
+        Ok(None) => {
+            warn!("No verified ABI found for implementation {} of {} on {}", implementation_str, address_str, chain);
+            Ok(None)
+        }
+        Err(e) if e.class.is_fatal() => {
+            Err(anyhow!("Aborting, failed to fetch ABI for implementation {} on {}: {}", implementation_str, chain, e))
+        }
+        Err(e) => {
+            warn!("Failed to fetch ABI for implementation {} of {} on {}: {}", implementation_str, address_str, chain, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Lists a diamond's facets and fetches each facet's ABI. As with proxies, only
+/// fatal failures abort the run; other failures skip the facet.
+async fn fetch_facets<S: AbiSource + ?Sized>(source: &S, resolver: &DiamondResolver, entry: &AddressEntry, options: &DownloadOptions)
+-> Result<Vec<(Facet, JsonAbi)>> {
+    let (chain, address) = (entry.chain, entry.address);
+    let address_str = format_address(&address);
+    let facets = match resolver.facets(chain, address).await {
+        Ok(facets) => facets,
+        Err(e) => {
+            warn!("Failed to list facets of diamond {} on {}: {}", address_str, chain, e);
+            return Ok(Vec::new());
+        }
+    };
+    info!("Address {} on {} is a diamond with {} facets", address_str, chain, facets.len());
+
+    let mut facet_abis = Vec::new();
+    for facet in facets {
+        if facet.address == address {
+            continue;
+        }
+        let facet_str = format_address(&facet.address);
+        match fetch_abi(source, options, chain, facet.address).await {
+            Ok(Some(fetched)) => facet_abis.push((facet, fetched.abi)),
+            Ok(None) => {
+                warn!("No verified ABI found for facet {} of {} on {}", facet_str, address_str, chain);
+            }
+            Err(e) if e.class.is_fatal() => {
+                return Err(anyhow!("Aborting, failed to fetch ABI for facet {} on {}: {}", facet_str, chain, e));
+            }
             Err(e) => {
-                print!("\n");
-                warn!("Failed to fetch ABI for address {}: {}", address_str, e);
+                warn!("Failed to fetch ABI for facet {} of {} on {}: {}", facet_str, address_str, chain, e);
             }
         }
-        time::sleep(RATE_LIMIT).await;
     }
-    print!("\n");
-    Ok((function_files, event_files))
+    Ok(facet_abis)
 }
 
+/// Writes the per-contract function, event, error and metadata tables under `<output_dir>/<chain_id>`.
+pub fn write_contract_tables(chain_id: u64, address: &str, records: &ContractRecords, output_dir: &Path) -> Result<ContractFiles> {
+    let chain_dir = output_dir.join(chain_id.to_string());
+    let files = ContractFiles {
+        functions: chain_dir.join("functions").join(format!("{}_functions.parquet", address)),
+        events: chain_dir.join("events").join(format!("{}_events.parquet", address)),
+        errors: chain_dir.join("errors").join(format!("{}_errors.parquet", address)),
+        metadata: chain_dir.join("metadata").join(format!("{}_metadata.parquet", address)),
+        contracts: (!records.contracts.is_empty())
+            .then(|| chain_dir.join("contracts").join(format!("{}_contracts.parquet", address))),
+    };
+    write_parquet(&records.functions, &records.labels, &files.functions)?;
+    write_parquet(&records.events, &records.labels, &files.events)?;
+    write_parquet(&records.errors, &records.labels, &files.errors)?;
+    write_metadata_parquet(&records.metadata, &records.labels, &files.metadata)?;
+    if let Some(contracts) = &files.contracts {
+        write_contracts_parquet(&records.contracts, &records.labels, contracts)?;
+    }
+    Ok(files)
+}
 
+/// Builds the tables from ABIs read off disk. Every record carries the file it
+/// was read from as `artifact_path`. Contracts without a recorded address get an
+/// empty `contract_address`, and their tables are named after that path.
+pub fn process_local_abis(abis: &[LocalAbi], chain: Chain, output_dir: &Path) -> Result<TableFiles> {
+    create_chain_dirs(output_dir, chain.id())?;
 
-pub fn process_contract(address: &str, abi_json: &JsonAbi) -> Result<(Vec<AbiRecord>, Vec<AbiRecord>)> {
-    let function_records = abi_json.functions()
-    .map(|f| {
+    let mut table_files = TableFiles::default();
+    let mut report = RunReport::default();
+    let mut seen = HashSet::new();
+    let total = abis.len();
+    for (index, local) in abis.iter().enumerate() {
+        let artifact_path = local.path.to_string_lossy().into_owned();
+        let (address, key) = match &local.address {
+            Some(address) => (format_address(address), format_address(address)),
+            None => (String::new(), artifact_file_key(&artifact_path)),
+        };
+        if !seen.insert(key.clone()) {
+            warn!("Skipping {}, {} was already read from another file", artifact_path, key);
+            continue;
+        }
+        info!("Processing ABI for {} from {} ({}/{})", local.name, artifact_path, index + 1, total);
+        let mut records = process_contract(chain.id(), &address, &local.abi)?;
+        records.set_contract_name(Some(local.name.clone()));
+        records.labels = vec![("artifact_path".to_string(), Some(artifact_path.clone()))];
+        warn_violations(&artifact_path, &records.violations);
+        table_files.push(write_contract_tables(chain.id(), &key, &records, output_dir)?);
+        report.succeeded += 1;
+        report.selector_violations.extend(records.violations);
+    }
+    report.write(output_dir)?;
+    Ok(table_files)
+}
+
+/// Table files of an artifact without an address are named after its path, with
+/// anything but letters, digits, `.` and `-` replaced by `_`.
+fn artifact_file_key(artifact_path: &str) -> String {
+    artifact_path
+        .trim_start_matches(['.', '/', '\\'])
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+fn warn_violations(contract: &str, violations: &[SelectorViolation]) {
+    for violation in violations {
+        warn!("Broken ABI for {}: {} selector {} is shared by {}",
+            contract, violation.record_type, violation.selector, violation.signatures.join(", "));
+    }
+}
+
+pub fn process_contract(chain_id: u64, address: &str, abi_json: &JsonAbi) -> Result<ContractRecords> {
+    let function_records = abi_json.functions.values()
+    .flat_map(|overloads| overloads.iter().enumerate())
+    .map(|(overload_index, f)| {
         AbiRecord {
+            chain_id,
             name: f.name.clone(),
+            overload_index: overload_index as u32,
+            display_name: create_display_name(&f.name, f.inputs.iter().map(|p| (p.selector_type(), p.name.as_str()))),
             record_type: "function".to_string(),
             contract_address: address.to_lowercase(),
             signature: create_function_signature(f),
-            selector: create_function_selector(f)
+            selector: Some(create_function_selector(f)),
+            state_mutability: Some(state_mutability_str(f.state_mutability).to_string()),
+            input_names: f.inputs.iter().map(|p| p.name.clone()).collect(),
+            input_types: f.inputs.iter().map(|p| p.selector_type().into_owned()).collect(),
+            output_names: f.outputs.iter().map(|p| p.name.clone()).collect(),
+            output_types: f.outputs.iter().map(|p| p.selector_type().into_owned()).collect(),
+            input_tree: param_tree(&f.inputs),
+            output_tree: param_tree(&f.outputs),
+            inputs_json: serde_json::to_string(&f.inputs).unwrap_or_default(),
+            outputs_json: serde_json::to_string(&f.outputs).ok(),
+            ..AbiRecord::default()
         }
     }).collect::<Vec<_>>();
 
-    let event_records = abi_json.events()
-    .map(|e| {
+    let event_records = abi_json.events.values()
+    .flat_map(|overloads| overloads.iter().enumerate())
+    .map(|(overload_index, e)| {
         AbiRecord {
+            chain_id,
             name: e.name.clone(),
+            overload_index: overload_index as u32,
+            display_name: create_display_name(&e.name, e.inputs.iter().map(|p| (p.selector_type(), p.name.as_str()))),
             record_type: "event".to_string(),
             contract_address: address.to_lowercase(),
             signature: create_event_signature(e),
-            selector: create_event_selector(e)
+            selector: (!e.anonymous).then(|| create_event_selector(e)),
+            input_names: e.inputs.iter().map(|p| p.name.clone()).collect(),
+            input_types: e.inputs.iter().map(|p| p.selector_type().into_owned()).collect(),
+            indexed: e.inputs.iter().map(|p| p.indexed).collect(),
+            anonymous: Some(e.anonymous),
+            indexed_topics: Some(e.inputs.iter().filter(|p| p.indexed).count() as u32),
+            input_tree: event_param_tree(&e.inputs),
+            inputs_json: serde_json::to_string(&e.inputs).unwrap_or_default(),
+            ..AbiRecord::default()
+        }
+    }).collect::<Vec<_>>();
+
+    let error_records = abi_json.errors.values()
+    .flat_map(|overloads| overloads.iter().enumerate())
+    .map(|(overload_index, e)| {
+        AbiRecord {
+            chain_id,
+            name: e.name.clone(),
+            overload_index: overload_index as u32,
+            display_name: create_display_name(&e.name, e.inputs.iter().map(|p| (p.selector_type(), p.name.as_str()))),
+            record_type: "error".to_string(),
+            contract_address: address.to_lowercase(),
+            signature: create_error_signature(e),
+            selector: Some(create_error_selector(e)),
+            input_names: e.inputs.iter().map(|p| p.name.clone()).collect(),
+            input_types: e.inputs.iter().map(|p| p.selector_type().into_owned()).collect(),
+            input_tree: param_tree(&e.inputs),
+            inputs_json: serde_json::to_string(&e.inputs).unwrap_or_default(),
+            ..AbiRecord::default()
         }
     }).collect::<Vec<_>>();
     
 
-    Ok((function_records, event_records))
+    let metadata = ContractMetadata {
+        chain_id,
+        contract_address: address.to_lowercase(),
+        contract_name: None,
+        constructor_inputs: abi_json.constructor.iter()
+            .flat_map(|c| c.inputs.iter().map(|input| input.selector_type().into_owned()))
+            .collect(),
+        constructor_payable: abi_json.constructor.as_ref()
+            .map(|c| c.state_mutability == StateMutability::Payable),
+        has_fallback: abi_json.fallback.is_some(),
+        fallback_mutability: abi_json.fallback.as_ref()
+            .map(|f| state_mutability_str(f.state_mutability).to_string()),
+        has_receive: abi_json.receive.is_some(),
+        receive_mutability: abi_json.receive.as_ref()
+            .map(|r| state_mutability_str(r.state_mutability).to_string()),
+    };
+
+    let mut records = ContractRecords {
+        functions: function_records,
+        events: event_records,
+        errors: error_records,
+        metadata: vec![metadata],
+        contracts: Vec::new(),
+        labels: Vec::new(),
+        violations: Vec::new(),
+    };
+    records.violations = selector_violations(&records);
+    Ok(records)
 }
 
-pub fn create_empty_record(address: &str) -> AbiRecord {
+/// Joins `(type, name)` pairs into e.g. `transfer(address to, uint256 amount)`,
+/// leaving out names of unnamed parameters.
+pub fn create_display_name<'a, T: AsRef<str>>(name: &str, params: impl Iterator<Item = (T, &'a str)>) -> String {
+    let params = params
+        .map(|(ty, param_name)| {
+            if param_name.is_empty() {
+                ty.as_ref().to_string()
+            } else {
+                format!("{} {}", ty.as_ref(), param_name)
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{}({})", name, params.join(", "))
+}
+
+pub fn param_tree(params: &[Param]) -> Vec<ParamNode> {
+    let mut nodes = Vec::new();
+    for (index, param) in params.iter().enumerate() {
+        push_param_nodes(&index.to_string(), &param.name, &param.ty, param.internal_type.as_ref(), &param.components, &mut nodes);
+    }
+    nodes
+}
+
+pub fn event_param_tree(params: &[EventParam]) -> Vec<ParamNode> {
+    let mut nodes = Vec::new();
+    for (index, param) in params.iter().enumerate() {
+        push_param_nodes(&index.to_string(), &param.name, &param.ty, param.internal_type.as_ref(), &param.components, &mut nodes);
+    }
+    nodes
+}
+
+fn push_param_nodes(path: &str, name: &str, ty: &str, internal_type: Option<&InternalType>, components: &[Param], nodes: &mut Vec<ParamNode>) {
+    nodes.push(ParamNode {
+        path: path.to_string(),
+        name: name.to_string(),
+        ty: ty.to_string(),
+        // serialized the same way as `internalType` in the ABI JSON
+        internal_type: internal_type
+            .and_then(|t| serde_json::to_value(t).ok())
+            .and_then(|v| v.as_str().map(String::from)),
+    });
+    for (index, component) in components.iter().enumerate() {
+        let component_path = format!("{}.{}", path, index);
+        push_param_nodes(&component_path, &component.name, &component.ty, component.internal_type.as_ref(), &component.components, nodes);
+    }
+}
+
+pub fn state_mutability_str(state_mutability: StateMutability) -> &'static str {
+    match state_mutability {
+        StateMutability::Pure => "pure",
+        StateMutability::View => "view",
+        StateMutability::NonPayable => "nonpayable",
+        StateMutability::Payable => "payable",
+    }
+}
+
+pub fn create_empty_record(chain_id: u64, address: &str) -> AbiRecord {
     AbiRecord {
-        record_type: String::new(),
+        chain_id,
         contract_address: address.to_string(),
-        name: String::new(),
-        signature: String::new(),
-        selector: String::new(),
+        ..AbiRecord::default()
     }
 }
 
@@ -170,6 +1060,14 @@ pub fn create_event_signature(e: &Event) -> String {
     format!("{}({})", e.name, input_types.join(","))
 }
 
+pub fn create_error_signature(e: &AbiError) -> String {
+    let input_types: Vec<String> = e.inputs.iter()
+        .filter_map(|input| input.selector_type().into())
+        .map(String::from)
+        .collect();
+    format!("{}({})", e.name, input_types.join(","))
+}
+
 pub fn create_function_selector(f: &Function) -> String {
     let signature = create_function_signature(f);
     let mut keccak = Keccak::v256();
@@ -186,4 +1084,144 @@ pub fn create_event_selector(e: &Event) -> String {
     let mut output = [0u8; 32];
     keccak.finalize(&mut output);
     format!("0x{}", hex::encode(output))
-}
\ No newline at end of file
+}
+
+pub fn create_error_selector(e: &AbiError) -> String {
+    let signature = create_error_signature(e);
+    let mut keccak = Keccak::v256();
+    keccak.update(signature.as_bytes());
+    let mut output = [0u8; 32];
+    keccak.finalize(&mut output);
+    format!("0x{}", hex::encode(&output[..4]))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL_ADDRESSES_PROVIDER: &str = "0x2f39d218133afab8f2b819b1066c7e434ad94e9e";
+
+    #[test]
+    fn parses_address_lines() {
+        let address = Address::from_str(POOL_ADDRESSES_PROVIDER).unwrap();
+
+        let entry = parse_address_line(POOL_ADDRESSES_PROVIDER, Chain::mainnet()).unwrap();
+        assert_eq!((entry.chain, entry.address, entry.label), (Chain::mainnet(), address, None));
+
+        let entry = parse_address_line(&format!("137:{}", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).unwrap();
+        assert_eq!((entry.chain, entry.address), (Chain::from_id(137), address));
+
+        let entry = parse_address_line(&format!("137, {}", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).unwrap();
+        assert_eq!((entry.chain, entry.address), (Chain::from_id(137), address));
+    }
+
+    #[test]
+    fn parses_address_lines_with_labels() {
+        let entry = parse_address_line(&format!("137:{},PoolAddressesProvider", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).unwrap();
+        assert_eq!(entry.chain, Chain::from_id(137));
+        assert_eq!(entry.label.as_deref(), Some("PoolAddressesProvider"));
+
+        let entry = parse_address_line(&format!("{},PoolAddressesProvider", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).unwrap();
+        assert_eq!(entry.chain, Chain::mainnet());
+        assert_eq!(entry.label.as_deref(), Some("PoolAddressesProvider"));
+
+        let entry = parse_address_line(&format!("1,{},", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).unwrap();
+        assert_eq!(entry.label, None);
+    }
+
+    #[test]
+    fn rejects_header_and_malformed_lines() {
+        assert!(parse_address_line("address", Chain::mainnet()).is_err());
+        assert!(parse_address_line("chain,address", Chain::mainnet()).is_err());
+        assert!(parse_address_line("chain,address,label", Chain::mainnet()).is_err());
+        assert!(parse_address_line("0x1234", Chain::mainnet()).is_err());
+        assert!(parse_address_line(&format!("nochain:{}", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).is_err());
+        assert!(parse_address_line(&format!("1,{},a,b", POOL_ADDRESSES_PROVIDER), Chain::mainnet()).is_err());
+    }
+
+    #[test]
+    fn keeps_source_paths_inside_the_source_dir() {
+        let path = |components: &[&str]| components.iter().collect::<PathBuf>();
+        assert_eq!(sanitize_source_path("contracts/Token.sol"), Some(path(&["contracts", "Token.sol"])));
+        assert_eq!(
+            sanitize_source_path("@openzeppelin/contracts/token/ERC20/ERC20.sol"),
+            Some(path(&["@openzeppelin", "contracts", "token", "ERC20", "ERC20.sol"])),
+        );
+        assert_eq!(sanitize_source_path("../../etc/passwd"), Some(path(&["etc", "passwd"])));
+        assert_eq!(sanitize_source_path("./src/../Token.sol"), Some(path(&["src", "Token.sol"])));
+        assert_eq!(sanitize_source_path("/src/Token.sol"), Some(path(&["src", "Token.sol"])));
+        assert_eq!(sanitize_source_path("C:\\Users\\dev\\Token.sol"), Some(path(&["C_", "Users", "dev", "Token.sol"])));
+        assert_eq!(sanitize_source_path("C:Token.sol"), Some(path(&["C_Token.sol"])));
+        assert_eq!(sanitize_source_path("../"), None);
+        assert_eq!(sanitize_source_path("/"), None);
+        assert_eq!(sanitize_source_path(""), None);
+    }
+
+    #[test]
+    fn keys_artifacts_without_an_address_by_path() {
+        assert_eq!(artifact_file_key("a/out/Token.sol/Token.json"), "a_out_Token.sol_Token.json");
+        assert_eq!(artifact_file_key("./b/out/Token.sol/Token.json"), "b_out_Token.sol_Token.json");
+        assert_eq!(artifact_file_key("C:\\artifacts\\Token.json"), "C__artifacts_Token.json");
+    }
+
+    fn abi(json: &str) -> JsonAbi {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn leaves_topic0_null_for_anonymous_events() {
+        let abi = abi(r#"[
+            {"type":"event","name":"Logged","anonymous":true,"inputs":[
+                {"name":"sender","type":"address","indexed":true},
+                {"name":"id","type":"uint256","indexed":true},
+                {"name":"data","type":"bytes","indexed":false}
+            ]},
+            {"type":"event","name":"Transfer","anonymous":false,"inputs":[
+                {"name":"from","type":"address","indexed":true},
+                {"name":"to","type":"address","indexed":true},
+                {"name":"value","type":"uint256","indexed":false}
+            ]}
+        ]"#);
+        let records = process_contract(1, POOL_ADDRESSES_PROVIDER, &abi).unwrap();
+        let event = |name: &str| records.events.iter().find(|record| record.name == name).unwrap();
+
+        let logged = event("Logged");
+        assert_eq!(logged.selector, None);
+        assert_eq!(logged.anonymous, Some(true));
+        assert_eq!(logged.indexed_topics, Some(2));
+        assert_eq!(logged.indexed, vec![true, true, false]);
+        assert_eq!(logged.signature, "Logged(address,uint256,bytes)");
+
+        let transfer = event("Transfer");
+        assert_eq!(transfer.selector.as_deref(), Some("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+        assert_eq!(transfer.anonymous, Some(false));
+        assert_eq!(transfer.indexed_topics, Some(2));
+        assert!(records.violations.is_empty());
+    }
+
+    #[test]
+    fn numbers_overloads_and_names_their_parameters() {
+        let abi = abi(r#"[
+            {"type":"function","name":"safeTransferFrom","stateMutability":"nonpayable","outputs":[],"inputs":[
+                {"name":"from","type":"address"},{"name":"to","type":"address"},{"name":"tokenId","type":"uint256"}
+            ]},
+            {"type":"function","name":"safeTransferFrom","stateMutability":"nonpayable","outputs":[],"inputs":[
+                {"name":"from","type":"address"},{"name":"to","type":"address"},{"name":"tokenId","type":"uint256"},{"name":"","type":"bytes"}
+            ]},
+            {"type":"function","name":"approve","stateMutability":"nonpayable","outputs":[],"inputs":[
+                {"name":"to","type":"address"},{"name":"tokenId","type":"uint256"}
+            ]}
+        ]"#);
+        let records = process_contract(1, POOL_ADDRESSES_PROVIDER, &abi).unwrap();
+        let mut functions = records.functions
+            .iter()
+            .map(|record| (record.overload_index, record.display_name.as_str(), record.selector.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        functions.sort();
+        assert_eq!(functions, vec![
+            (0, "approve(address to, uint256 tokenId)", "0x095ea7b3"),
+            (0, "safeTransferFrom(address from, address to, uint256 tokenId)", "0x42842e0e"),
+            (1, "safeTransferFrom(address from, address to, uint256 tokenId, bytes)", "0xb88d4fde"),
+        ]);
+        assert!(records.violations.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use alloy_chains::Chain;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::abi_downloader::format_address;
+use crate::config::Config;
+use crate::rate_limit::RateLimiter;
+use crate::source::AbiSource;
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    status: String,
+    message: String,
+    result: Option<String>,
+}
+
+struct BlockscoutInstance {
+    api_url: String,
+    limiter: RateLimiter,
+}
+
+/// Blockscout explorers, queried through their Etherscan-style `getabi` endpoint.
+/// Only chains with a `blockscout_url` in their config section are looked up.
+pub struct BlockscoutSource {
+    instances: HashMap<u64, BlockscoutInstance>,
+    http: reqwest::Client,
+}
+
+impl BlockscoutSource {
+    pub fn new(config: &Config) -> Result<Self> {
+        let instances = config.chains.iter()
+            .filter_map(|(chain_id, chain_config)| {
+                let api_url = chain_config.blockscout_url.clone()?;
+                let limiter = RateLimiter::new(chain_config.rate_limit);
+                Some((*chain_id, BlockscoutInstance { api_url, limiter }))
+            })
+            .collect::<HashMap<_, _>>();
+        if instances.is_empty() {
+            return Err(anyhow!("No chain has a blockscout_url in the config file"));
+        }
+        Ok(BlockscoutSource { instances, http: reqwest::Client::new() })
+    }
+}
+
+#[async_trait]
+impl AbiSource for BlockscoutSource {
+    fn name(&self) -> &str {
+        "blockscout"
+    }
+
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>> {
+        let Some(instance) = self.instances.get(&chain.id()) else {
+            return Ok(None);
+        };
+        instance.limiter.acquire().await;
+        let address_str = format_address(&address);
+        let response = self.http.get(&instance.api_url)
+            .query(&[("module", "contract"), ("action", "getabi"), ("address", address_str.as_str())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ApiResponse>()
+            .await?;
+
+        if response.status != "1" {
+            let message = response.message.to_lowercase();
+            if message.contains("not verified") || message.contains("not found") {
+                return Ok(None);
+            }
+            return Err(anyhow!("Blockscout error for {}: {}", address_str, response.message));
+        }
+        let abi = response.result
+            .ok_or_else(|| anyhow!("Blockscout response for {} has no result", address_str))?;
+        Ok(Some(serde_json::from_str(&abi)?))
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use alloy_json_abi::JsonAbi;
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::source::ContractSource;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedAbi {
+    /// Unix timestamp, in seconds, of the request that returned `abi`.
+    fetched_at: u64,
+    abi: JsonAbi,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSource {
+    fetched_at: u64,
+    /// `None` records that the explorer had no source, so it isn't asked again.
+    source: Option<ContractSource>,
+}
+
+/// Raw ABIs as returned by the explorer, stored as `<dir>/<chain_id>/<address>.json`,
+/// and source metadata, stored next to them as `<address>.source.json`.
+#[derive(Debug, Clone)]
+pub struct AbiCache {
+    dir: PathBuf,
+    max_age: Option<Duration>,
+    refresh: bool,
+}
+
+impl AbiCache {
+    /// Entries older than `max_age` are ignored, and `refresh` ignores every
+    /// entry while still writing fresh downloads back to the cache.
+    pub fn new(dir: &Path, max_age: Option<Duration>, refresh: bool) -> Self {
+        AbiCache { dir: dir.to_path_buf(), max_age, refresh }
+    }
+
+    pub fn path(&self, chain_id: u64, address: &str) -> PathBuf {
+        self.dir.join(chain_id.to_string()).join(format!("{}.json", address))
+    }
+
+    pub fn source_path(&self, chain_id: u64, address: &str) -> PathBuf {
+        self.dir.join(chain_id.to_string()).join(format!("{}.source.json", address))
+    }
+
+    pub fn get(&self, chain_id: u64, address: &str) -> Option<JsonAbi> {
+        let cached: CachedAbi = self.read(&self.path(chain_id, address))?;
+        self.is_fresh(cached.fetched_at).then_some(cached.abi)
+    }
+
+    pub fn put(&self, chain_id: u64, address: &str, abi: &JsonAbi) -> Result<()> {
+        let cached = CachedAbi { fetched_at: unix_now(), abi: abi.clone() };
+        write(&self.path(chain_id, address), &cached)
+    }
+
+    /// The outer `None` is a cache miss, the inner one a contract without source.
+    pub fn get_source(&self, chain_id: u64, address: &str) -> Option<Option<ContractSource>> {
+        let cached: CachedSource = self.read(&self.source_path(chain_id, address))?;
+        self.is_fresh(cached.fetched_at).then_some(cached.source)
+    }
+
+    pub fn put_source(&self, chain_id: u64, address: &str, source: Option<&ContractSource>) -> Result<()> {
+        let cached = CachedSource { fetched_at: unix_now(), source: source.cloned() };
+        write(&self.source_path(chain_id, address), &cached)
+    }
+
+    fn read<T: DeserializeOwned>(&self, path: &Path) -> Option<T> {
+        if self.refresh {
+            return None;
+        }
+        let contents = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(cached) => Some(cached),
+            Err(e) => {
+                warn!("Ignoring corrupt cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn is_fresh(&self, fetched_at: u64) -> bool {
+        match self.max_age {
+            Some(max_age) => unix_now().saturating_sub(fetched_at) <= max_age.as_secs(),
+            None => true,
+        }
+    }
+}
+
+fn write<T: Serialize>(path: &Path, cached: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("failed to create cache dir {}: {}", parent.display(), e))?;
+    }
+    // write then rename so concurrent runs never read a partial file
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(cached)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use crate::abi_downloader::list_column;
+
+pub const COLLISIONS_FILE_NAME: &str = "collisions.parquet";
+
+/// A contract declaring a function or error, as read from the combined tables.
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub chain_id: u64,
+    pub contract_address: String,
+    pub signature: String,
+    pub implementation_address: Option<String>,
+}
+
+/// A selector shared by more than one signature.
+#[derive(Debug)]
+pub struct Collision {
+    pub record_type: String,
+    pub selector: String,
+    pub signatures: Vec<String>,
+    pub declarations: Vec<Declaration>,
+    /// A proxy and its implementation declare the selector with different
+    /// signatures, so calls meant for the implementation hit the proxy instead.
+    pub proxy_clash: bool,
+}
+
+/// Scans the given `all_*.parquet` tables for selectors of the same record type
+/// that different signatures hash to. Missing tables are skipped.
+pub fn find_collisions(tables: &[PathBuf]) -> Result<Vec<Collision>> {
+    let mut by_selector = BTreeMap::<(String, String), Vec<Declaration>>::new();
+    for table in tables {
+        if !table.exists() {
+            continue;
+        }
+        for (record_type, selector, declaration) in read_declarations(table)? {
+            by_selector.entry((record_type, selector)).or_default().push(declaration);
+        }
+    }
+
+    let mut collisions = Vec::new();
+    for ((record_type, selector), declarations) in by_selector {
+        let signatures = declarations.iter().map(|d| d.signature.clone()).collect::<BTreeSet<_>>();
+        if signatures.len() < 2 {
+            continue;
+        }
+        let proxy_clash = is_proxy_clash(&declarations);
+        // a contract can be read more than once, e.g. as a proxy's implementation and on its own
+        let mut seen = BTreeSet::new();
+        let declarations = declarations
+            .into_iter()
+            .filter(|d| seen.insert((d.chain_id, d.contract_address.clone(), d.signature.clone())))
+            .collect();
+        collisions.push(Collision {
+            record_type,
+            selector,
+            signatures: signatures.into_iter().collect(),
+            declarations,
+            proxy_clash,
+        });
+    }
+    Ok(collisions)
+}
+
+fn read_declarations(path: &Path) -> Result<Vec<(String, String, Declaration)>> {
+    let df = ParquetReader::new(File::open(path)?)
+        .finish()
+        .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+    let record_types = df.column("record_type")?.str()?;
+    let selectors = df.column("selector")?.str()?;
+    let chain_ids = df.column("chain_id")?.u64()?;
+    let addresses = df.column("contract_address")?.str()?;
+    let signatures = df.column("signature")?.str()?;
+    let implementations = df.column("implementation_address")?.str()?;
+
+    let mut declarations = Vec::with_capacity(df.height());
+    for row in 0..df.height() {
+        let (Some(record_type), Some(selector), Some(chain_id), Some(address), Some(signature)) = (
+            record_types.get(row),
+            selectors.get(row),
+            chain_ids.get(row),
+            addresses.get(row),
+            signatures.get(row),
+        ) else {
+            continue;
+        };
+        declarations.push((
+            record_type.to_string(),
+            selector.to_string(),
+            Declaration {
+                chain_id,
+                contract_address: address.to_string(),
+                signature: signature.to_string(),
+                implementation_address: implementations.get(row).map(String::from),
+            },
+        ));
+    }
+    Ok(declarations)
+}
+
+fn is_proxy_clash(declarations: &[Declaration]) -> bool {
+    declarations.iter().any(|proxy| {
+        let Some(implementation) = &proxy.implementation_address else {
+            return false;
+        };
+        declarations.iter().any(|d| {
+            d.chain_id == proxy.chain_id && &d.contract_address == implementation && d.signature != proxy.signature
+        })
+    })
+}
+
+pub fn write_collisions(collisions: &[Collision], filename: &Path) -> Result<()> {
+    let declaration_type = DataType::Struct(vec![
+        Field::new("chain_id", DataType::UInt64),
+        Field::new("contract_address", DataType::String),
+        Field::new("signature", DataType::String),
+    ]);
+    let declarations = collisions
+        .iter()
+        .map(|c| {
+            let fields = [
+                Series::new("chain_id", c.declarations.iter().map(|d| d.chain_id).collect::<Vec<_>>()),
+                Series::new("contract_address", c.declarations.iter().map(|d| d.contract_address.clone()).collect::<Vec<_>>()),
+                Series::new("signature", c.declarations.iter().map(|d| d.signature.clone()).collect::<Vec<_>>()),
+            ];
+            Ok(StructChunked::new("", &fields)?.into_series())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut df = DataFrame::new(vec![
+        Series::new("record_type", collisions.iter().map(|c| c.record_type.clone()).collect::<Vec<_>>()),
+        Series::new("selector", collisions.iter().map(|c| c.selector.clone()).collect::<Vec<_>>()),
+        Series::new("signature_count", collisions.iter().map(|c| c.signatures.len() as u32).collect::<Vec<_>>()),
+        list_column("signatures", collisions.iter().map(|c| Series::new("", &c.signatures)).collect(), DataType::String)?,
+        list_column("declarations", declarations, declaration_type)?,
+        Series::new("proxy_clash", collisions.iter().map(|c| c.proxy_clash).collect::<Vec<_>>()),
+    ])?;
+
+    let file = File::create(filename)?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: &str = "0x0000000000000000000000000000000000000001";
+    const IMPLEMENTATION: &str = "0x0000000000000000000000000000000000000002";
+    const OTHER: &str = "0x0000000000000000000000000000000000000003";
+
+    fn declaration(address: &str, signature: &str, implementation: Option<&str>) -> Declaration {
+        Declaration {
+            chain_id: 1,
+            contract_address: address.to_string(),
+            signature: signature.to_string(),
+            implementation_address: implementation.map(String::from),
+        }
+    }
+
+    #[test]
+    fn detects_proxy_clashes() {
+        let clash = [declaration(PROXY, "a()", Some(IMPLEMENTATION)), declaration(IMPLEMENTATION, "b()", None)];
+        assert!(is_proxy_clash(&clash));
+
+        // the implementation declaring the proxy's own signature is not a clash
+        let same = [declaration(PROXY, "a()", Some(IMPLEMENTATION)), declaration(IMPLEMENTATION, "a()", None)];
+        assert!(!is_proxy_clash(&same));
+
+        // nor are unrelated contracts, or the implementation on another chain
+        let unrelated = [declaration(PROXY, "a()", None), declaration(OTHER, "b()", None)];
+        assert!(!is_proxy_clash(&unrelated));
+        let mut other_chain = declaration(IMPLEMENTATION, "b()", None);
+        other_chain.chain_id = 10;
+        assert!(!is_proxy_clash(&[declaration(PROXY, "a()", Some(IMPLEMENTATION)), other_chain]));
+    }
+
+    #[test]
+    fn finds_collisions_in_combined_tables() {
+        // made-up signatures; only the selectors they are filed under matter here
+        let rows: [(&str, &str, &str, &str, Option<&str>); 7] = [
+            ("function", "0x12345678", PROXY, "a()", Some(IMPLEMENTATION)),
+            ("function", "0x12345678", IMPLEMENTATION, "b()", None),
+            ("function", "0x12345678", IMPLEMENTATION, "b()", None),
+            ("function", "0x87654321", PROXY, "c()", None),
+            ("function", "0x87654321", OTHER, "c()", None),
+            ("function", "0xabcdef01", OTHER, "d()", None),
+            ("error", "0xabcdef01", OTHER, "e()", None),
+        ];
+        let mut df = df![
+            "record_type" => rows.iter().map(|r| r.0).collect::<Vec<_>>(),
+            "selector" => rows.iter().map(|r| r.1).collect::<Vec<_>>(),
+            "chain_id" => rows.iter().map(|_| 1u64).collect::<Vec<_>>(),
+            "contract_address" => rows.iter().map(|r| r.2).collect::<Vec<_>>(),
+            "signature" => rows.iter().map(|r| r.3).collect::<Vec<_>>(),
+            "implementation_address" => rows.iter().map(|r| r.4).collect::<Vec<_>>(),
+        ].unwrap();
+        let path = std::env::temp_dir().join(format!("collisions-test-{}.parquet", std::process::id()));
+        ParquetWriter::new(File::create(&path).unwrap()).finish(&mut df).unwrap();
+        let missing = std::env::temp_dir().join("collisions-test-missing.parquet");
+        let collisions = find_collisions(&[path.clone(), missing]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // the same selector with the same signature, or in a different record type, is no collision
+        assert_eq!(collisions.len(), 1);
+        let collision = &collisions[0];
+        assert_eq!((collision.record_type.as_str(), collision.selector.as_str()), ("function", "0x12345678"));
+        assert_eq!(collision.signatures, vec!["a()", "b()"]);
+        assert!(collision.proxy_clash);
+        let declared = collision.declarations.iter().map(|d| (d.contract_address.as_str(), d.signature.as_str())).collect::<Vec<_>>();
+        assert_eq!(declared, vec![(PROXY, "a()"), (IMPLEMENTATION, "b()")]);
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use configparser::ini::Ini;
+use alloy_chains::Chain;
+use anyhow::{anyhow, Result};
+
+const CHAIN_SECTION_PREFIX: &str = "chain.";
+const DEFAULT_CALLS_PER_SECOND: f64 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct ChainConfig {
+    /// Explorer API key, only required by the etherscan source.
+    pub api_key: Option<String>,
+    pub api_url: Option<String>,
+    pub browser_url: Option<String>,
+    /// Etherscan-compatible API of the chain's Blockscout explorer, e.g. `https://eth.blockscout.com/api`.
+    pub blockscout_url: Option<String>,
+    /// JSON-RPC endpoint, used to read proxy storage slots.
+    pub rpc_url: Option<String>,
+    /// Maximum number of API calls per second for this chain's key.
+    pub rate_limit: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// `ETHERSCAN_API_KEY` from `[api_keys]`, used by chains without their own key.
+    pub default_api_key: Option<String>,
+    pub chains: HashMap<u64, ChainConfig>,
+}
+
+impl Config {
+    pub fn chain_config(&self, chain: Chain) -> Result<ChainConfig> {
+        if let Some(chain_config) = self.chains.get(&chain.id()) {
+            return Ok(chain_config.clone());
+        }
+        match &self.default_api_key {
+            Some(api_key) => Ok(ChainConfig {
+                api_key: Some(api_key.clone()),
+                api_url: None,
+                browser_url: None,
+                blockscout_url: None,
+                rpc_url: None,
+                rate_limit: DEFAULT_CALLS_PER_SECOND,
+            }),
+            None => Err(anyhow!(
+                "chain {} (id {}) is not configured: add a [chain.{}] section with an api_key, or set ETHERSCAN_API_KEY under [api_keys]",
+                chain, chain.id(), chain
+            )),
+        }
+    }
+}
+
+pub fn read_config(config_path: &str) -> Result<Config> {
+    let sections = Ini::new().load(config_path)
+        .map_err(|e| anyhow!("Failed to load config file: {}", e))?;
+
+    let default_api_key = sections.get("api_keys")
+        .and_then(|keys| keys.get(&"ETHERSCAN_API_KEY".to_lowercase()).cloned().flatten());
+
+    let mut chains = HashMap::new();
+    for (section, values) in &sections {
+        let Some(chain_name) = section.strip_prefix(CHAIN_SECTION_PREFIX) else {
+            continue;
+        };
+        let chain = Chain::from_str(chain_name)
+            .map_err(|_| anyhow!("Unknown chain '{}' in section [{}]", chain_name, section))?;
+        let value = |key: &str| values.get(key).cloned().flatten();
+
+        // sections that only set e.g. rpc_url or blockscout_url need no key
+        let api_key = value("api_key").or_else(|| default_api_key.clone());
+        let rate_limit = match value("rate_limit") {
+            Some(v) => v.parse::<f64>().ok().filter(|r| *r > 0.0)
+                .ok_or_else(|| anyhow!("Invalid rate_limit '{}' in section [{}], expected calls per second", v, section))?,
+            None => DEFAULT_CALLS_PER_SECOND,
+        };
+
+        chains.insert(chain.id(), ChainConfig {
+            api_key,
+            api_url: value("api_url"),
+            browser_url: value("browser_url"),
+            blockscout_url: value("blockscout_url"),
+            rpc_url: value("rpc_url"),
+            rate_limit,
+        });
+    }
+
+    if default_api_key.is_none() && chains.is_empty() {
+        return Err(anyhow!("Could not find ETHERSCAN_API_KEY or any [chain.<name>] section in config file"));
+    }
+
+    Ok(Config { default_api_key, chains })
+}
@@ -0,0 +1,696 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use alloy_dyn_abi::{DynSolType, DynSolValue, EventExt, JsonAbiExt};
+use alloy_json_abi::{Event, EventParam, Function, Param, StateMutability};
+use alloy_primitives::B256;
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use serde::Serialize;
+
+/// A signature from one of the combined tables, with what is needed to decode it.
+#[derive(Debug, Clone)]
+pub struct IndexedSignature {
+    pub name: String,
+    pub signature: String,
+    pub inputs_json: String,
+}
+
+/// Where the signature used for decoding came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSource {
+    /// The ABI of the called contract, or of the implementation or facet it routes to.
+    Contract,
+    /// Any downloaded contract with the same selector.
+    Global,
+}
+
+impl MatchSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchSource::Contract => "contract",
+            MatchSource::Global => "global",
+        }
+    }
+}
+
+/// Selector lookup over an `all_*.parquet` table, per contract and across all
+/// contracts. A proxy's entry also holds its implementation's signatures, since
+/// the implementation records carry the proxy in `proxy_address`.
+#[derive(Debug, Default)]
+pub struct SignatureIndex {
+    signatures: Vec<IndexedSignature>,
+    by_contract: HashMap<(u64, String), HashMap<String, Vec<usize>>>,
+    /// Distinct signatures per selector, most frequently declared first.
+    global: HashMap<String, Vec<usize>>,
+}
+
+impl SignatureIndex {
+    pub fn read(path: &Path) -> Result<Self> {
+        let df = ParquetReader::new(File::open(path)?)
+            .finish()
+            .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        let chain_ids = df.column("chain_id")?.u64()?;
+        let addresses = df.column("contract_address")?.str()?;
+        let proxies = df.column("proxy_address")?.str()?;
+        let names = df.column("name")?.str()?;
+        let signatures = df.column("signature")?.str()?;
+        let selectors = df.column("selector")?.str()?;
+        let inputs = df.column("inputs_json")?.str()?;
+
+        let mut index = SignatureIndex::default();
+        let mut by_signature = HashMap::new();
+        let mut occurrences = HashMap::<usize, usize>::new();
+        for row in 0..df.height() {
+            let (Some(chain_id), Some(address), Some(name), Some(signature), Some(selector), Some(inputs_json)) = (
+                chain_ids.get(row),
+                addresses.get(row),
+                names.get(row),
+                signatures.get(row),
+                selectors.get(row),
+                inputs.get(row),
+            ) else {
+                continue;
+            };
+            let position = index.signatures.len();
+            index.signatures.push(IndexedSignature {
+                name: name.to_string(),
+                signature: signature.to_string(),
+                inputs_json: inputs_json.to_string(),
+            });
+
+            for owner in [Some(address), proxies.get(row)].into_iter().flatten() {
+                index.by_contract
+                    .entry((chain_id, owner.to_lowercase()))
+                    .or_default()
+                    .entry(selector.to_string())
+                    .or_default()
+                    .push(position);
+            }
+
+            // events can share a signature but index different parameters, such as the
+            // ERC-20 and ERC-721 `Transfer`, so both layouts are kept as candidates
+            let key = (signature.to_string(), indexed_flags(inputs_json));
+            let global_position = *by_signature.entry(key).or_insert_with(|| {
+                index.global.entry(selector.to_string()).or_default().push(position);
+                position
+            });
+            *occurrences.entry(global_position).or_default() += 1;
+        }
+        for positions in index.global.values_mut() {
+            positions.sort_by_key(|position| std::cmp::Reverse(occurrences[position]));
+        }
+        Ok(index)
+    }
+
+    /// Candidates for `selector`, from the contract at `address` when it declares
+    /// the selector, otherwise from every contract.
+    pub fn candidates(&self, chain_id: u64, address: Option<&str>, selector: &str) -> Vec<(&IndexedSignature, MatchSource)> {
+        let contract = address
+            .and_then(|address| self.by_contract.get(&(chain_id, address.to_lowercase())))
+            .and_then(|selectors| selectors.get(selector))
+            .into_iter()
+            .flatten()
+            .map(|&position| (&self.signatures[position], MatchSource::Contract));
+        let global = self.global
+            .get(selector)
+            .into_iter()
+            .flatten()
+            .map(|&position| (&self.signatures[position], MatchSource::Global));
+        contract.chain(global).collect()
+    }
+}
+
+/// Which parameters of an `inputs_json` list are indexed, all `false` for
+/// functions and errors.
+fn indexed_flags(inputs_json: &str) -> Vec<bool> {
+    serde_json::from_str::<Vec<serde_json::Value>>(inputs_json)
+        .map(|params| params.iter().map(|param| param["indexed"].as_bool().unwrap_or(false)).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedArgument {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedCall {
+    pub selector: String,
+    pub signature: String,
+    pub source: MatchSource,
+    pub arguments: Vec<DecodedArgument>,
+}
+
+/// Decodes calldata sent to `to` with the first candidate signature whose
+/// argument encoding it matches. Returns `None` when no signature fits.
+pub fn decode_calldata(index: &SignatureIndex, chain_id: u64, to: Option<&str>, calldata: &[u8]) -> Option<DecodedCall> {
+    if calldata.len() < 4 {
+        return None;
+    }
+    let selector = format!("0x{}", hex::encode(&calldata[..4]));
+    index.candidates(chain_id, to, &selector).into_iter().find_map(|(candidate, source)| {
+        let inputs = serde_json::from_str::<Vec<Param>>(&candidate.inputs_json).ok()?;
+        let function = Function {
+            name: candidate.name.clone(),
+            inputs,
+            outputs: Vec::new(),
+            state_mutability: StateMutability::NonPayable,
+        };
+        let values = function.abi_decode_input(&calldata[4..], true).ok()?;
+        Some(DecodedCall {
+            selector: selector.clone(),
+            signature: candidate.signature.clone(),
+            source,
+            arguments: decoded_arguments(&function.inputs, &values),
+        })
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedLog {
+    pub topic0: String,
+    pub name: String,
+    pub signature: String,
+    pub source: MatchSource,
+    pub arguments: Vec<DecodedArgument>,
+}
+
+/// Decodes a log emitted by `address` with the first candidate event whose
+/// indexed and non-indexed parameters match its topics and data. Anonymous
+/// events have no topic0 to look up and are never matched.
+pub fn decode_log(index: &SignatureIndex, chain_id: u64, address: Option<&str>, topics: &[B256], data: &[u8]) -> Option<DecodedLog> {
+    let topic0 = format!("0x{}", hex::encode(topics.first()?));
+    index.candidates(chain_id, address, &topic0).into_iter().find_map(|(candidate, source)| {
+        let inputs = serde_json::from_str::<Vec<EventParam>>(&candidate.inputs_json).ok()?;
+        let event = Event { name: candidate.name.clone(), inputs, anonymous: false };
+        let decoded = event.decode_log_parts(topics.iter().copied(), data, true).ok()?;
+
+        // indexed and non-indexed values come back separately, in parameter order
+        let mut indexed = decoded.indexed.iter();
+        let mut body = decoded.body.iter();
+        let arguments = event.inputs
+            .iter()
+            .map(|param| {
+                let value = if param.indexed { indexed.next() } else { body.next() }?;
+                Some(DecodedArgument {
+                    name: param.name.clone(),
+                    ty: param.selector_type().into_owned(),
+                    value: value_to_json(value),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(DecodedLog {
+            topic0: topic0.clone(),
+            name: candidate.name.clone(),
+            signature: candidate.signature.clone(),
+            source,
+            arguments,
+        })
+    })
+}
+
+// `Error(string)`, emitted by `require(condition, "reason")` and `revert("reason")`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// `Panic(uint256)`, emitted by failed asserts and checked arithmetic
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecodedRevert {
+    /// Reverted without data, e.g. `revert()` or `require(condition)`.
+    Empty,
+    Error { reason: String },
+    Panic { code: String, reason: String },
+    /// A custom error, looked up like calldata in the errors table.
+    Custom(DecodedCall),
+}
+
+/// Decodes the revert data of a call to `address`. Custom errors are only
+/// decoded when `errors` is an index over `all_errors.parquet`. Returns `None`
+/// when the data matches no known error.
+pub fn decode_revert(errors: Option<&SignatureIndex>, chain_id: u64, address: Option<&str>, data: &[u8]) -> Option<DecodedRevert> {
+    if data.is_empty() {
+        return Some(DecodedRevert::Empty);
+    }
+    if data.len() >= 4 && data[..4] == ERROR_SELECTOR {
+        if let Ok(DynSolValue::String(reason)) = DynSolType::String.abi_decode(&data[4..]) {
+            return Some(DecodedRevert::Error { reason });
+        }
+    }
+    if data.len() >= 4 && data[..4] == PANIC_SELECTOR {
+        if let Ok(DynSolValue::Uint(code, _)) = DynSolType::Uint(256).abi_decode(&data[4..]) {
+            return Some(DecodedRevert::Panic {
+                code: format!("{:#x}", code),
+                reason: u64::try_from(code).ok().and_then(panic_reason).unwrap_or("unknown panic code").to_string(),
+            });
+        }
+    }
+    decode_calldata(errors?, chain_id, address, data).map(DecodedRevert::Custom)
+}
+
+/// Reasons for the panic codes of the Solidity documentation.
+pub fn panic_reason(code: u64) -> Option<&'static str> {
+    let reason = match code {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "conversion to an invalid enum value",
+        0x22 => "incorrectly encoded storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to a zero-initialized internal function",
+        _ => return None,
+    };
+    Some(reason)
+}
+
+pub fn decoded_arguments(params: &[Param], values: &[DynSolValue]) -> Vec<DecodedArgument> {
+    params
+        .iter()
+        .zip(values)
+        .map(|(param, value)| DecodedArgument {
+            name: param.name.clone(),
+            ty: param.selector_type().into_owned(),
+            value: value_to_json(value),
+        })
+        .collect()
+}
+
+/// Integers are written as decimal strings, since JSON numbers cannot hold 256 bits.
+pub fn value_to_json(value: &DynSolValue) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        DynSolValue::Bool(b) => Value::Bool(*b),
+        DynSolValue::Int(i, _) => Value::String(i.to_string()),
+        DynSolValue::Uint(u, _) => Value::String(u.to_string()),
+        DynSolValue::FixedBytes(word, size) => Value::String(format!("0x{}", hex::encode(&word[..*size]))),
+        DynSolValue::Address(address) => Value::String(format!("0x{}", hex::encode(address.as_slice()))),
+        DynSolValue::Function(function) => Value::String(format!("0x{}", hex::encode(function.as_slice()))),
+        DynSolValue::Bytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+        DynSolValue::String(s) => Value::String(s.clone()),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) | DynSolValue::Tuple(values) => {
+            Value::Array(values.iter().map(value_to_json).collect())
+        }
+    }
+}
+
+pub fn parse_hex(data: &str) -> Result<Vec<u8>> {
+    let data = data.trim();
+    hex::decode(data.strip_prefix("0x").unwrap_or(data)).map_err(|e| anyhow!("invalid hex '{}': {}", data, e))
+}
+
+/// Reads a column of hex strings or raw binary, as transaction exports store either.
+pub fn bytes_column(df: &DataFrame, name: &str) -> Result<Vec<Option<Vec<u8>>>> {
+    let column = df.column(name)?;
+    match column.dtype() {
+        DataType::Binary => Ok(column.binary()?.into_iter().map(|value| value.map(<[u8]>::to_vec)).collect()),
+        DataType::String => column.str()?
+            .into_iter()
+            .map(|value| value.map(parse_hex).transpose())
+            .collect(),
+        dtype => Err(anyhow!("column {} has unsupported type {}", name, dtype)),
+    }
+}
+
+/// Reads the topics of each log from a `topics` list column, or from separate
+/// `topic0` to `topic3` columns as exported by cryo. Topics may be hex strings
+/// or binary.
+pub fn topics_column(df: &DataFrame) -> Result<Vec<Vec<B256>>> {
+    let columns = df.get_column_names();
+    if columns.contains(&"topics") {
+        return df.column("topics")?
+            .list()?
+            .into_iter()
+            .map(|topics| match topics {
+                Some(topics) => {
+                    let topics = DataFrame::new(vec![topics.with_name("topic")])?;
+                    bytes_column(&topics, "topic")?.into_iter().flatten().map(|topic| parse_topic(&topic)).collect()
+                }
+                None => Ok(Vec::new()),
+            })
+            .collect();
+    }
+
+    if !columns.contains(&"topic0") {
+        return Err(anyhow!("expected a topics column or topic0 to topic3 columns"));
+    }
+    let mut topics = vec![Vec::new(); df.height()];
+    for (position, name) in ["topic0", "topic1", "topic2", "topic3"].into_iter().enumerate() {
+        if !columns.contains(&name) {
+            continue;
+        }
+        for (log_topics, topic) in topics.iter_mut().zip(bytes_column(df, name)?) {
+            // a null topic ends the topics of a log
+            if let (Some(topic), true) = (topic, log_topics.len() == position) {
+                log_topics.push(parse_topic(&topic)?);
+            }
+        }
+    }
+    Ok(topics)
+}
+
+fn parse_topic(topic: &[u8]) -> Result<B256> {
+    B256::try_from(topic).map_err(|_| anyhow!("topic 0x{} is not 32 bytes", hex::encode(topic)))
+}
+
+/// Chain of each row, from a `chain_id` column when present.
+pub fn chain_id_column(df: &DataFrame, default_chain_id: u64) -> Result<Vec<u64>> {
+    if !df.get_column_names().contains(&"chain_id") {
+        return Ok(vec![default_chain_id; df.height()]);
+    }
+    let chain_ids = df.column("chain_id")?.cast(&DataType::UInt64)?;
+    Ok(chain_ids.u64()?.into_iter().map(|chain_id| chain_id.unwrap_or(default_chain_id)).collect())
+}
+
+/// Adds `decoded_selector`, `decoded_signature`, `decoded_match` and
+/// `decoded_arguments` (JSON) columns to a table with `to` and `input` columns.
+/// Returns the number of decoded rows.
+pub fn decode_transactions(index: &SignatureIndex, df: &mut DataFrame, default_chain_id: u64) -> Result<usize> {
+    let chain_ids = chain_id_column(df, default_chain_id)?;
+    let to = bytes_column(df, "to")?;
+    let input = bytes_column(df, "input")?;
+
+    let decoded = chain_ids
+        .iter()
+        .zip(&to)
+        .zip(&input)
+        .map(|((chain_id, to), input)| {
+            let to = to.as_ref().map(|to| format!("0x{}", hex::encode(to)));
+            input.as_ref().and_then(|input| decode_calldata(index, *chain_id, to.as_deref(), input))
+        })
+        .collect::<Vec<_>>();
+
+    df.with_column(Series::new("decoded_selector", decoded.iter().map(|d| d.as_ref().map(|d| d.selector.clone())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_signature", decoded.iter().map(|d| d.as_ref().map(|d| d.signature.clone())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_match", decoded.iter().map(|d| d.as_ref().map(|d| d.source.as_str())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_arguments", decoded.iter()
+        .map(|d| d.as_ref().map(|d| serde_json::to_string(&d.arguments)).transpose())
+        .collect::<serde_json::Result<Vec<_>>>()?))?;
+    Ok(decoded.iter().flatten().count())
+}
+
+pub fn decode_transactions_file(index: &SignatureIndex, input: &Path, output: &Path, default_chain_id: u64) -> Result<usize> {
+    let mut df = ParquetReader::new(File::open(input)?).finish()?;
+    let decoded = decode_transactions(index, &mut df, default_chain_id)?;
+    ParquetWriter::new(File::create(output)?).finish(&mut df)?;
+    Ok(decoded)
+}
+
+/// Adds `decoded_event`, `decoded_signature`, `decoded_match` and
+/// `decoded_arguments` (JSON) columns to a table of logs with `address`,
+/// topics and `data` columns. Returns the number of decoded rows.
+pub fn decode_logs(index: &SignatureIndex, df: &mut DataFrame, default_chain_id: u64) -> Result<usize> {
+    let chain_ids = chain_id_column(df, default_chain_id)?;
+    let addresses = bytes_column(df, "address")?;
+    let topics = topics_column(df)?;
+    let data = bytes_column(df, "data")?;
+
+    let decoded = chain_ids
+        .iter()
+        .zip(&addresses)
+        .zip(topics.iter().zip(&data))
+        .map(|((chain_id, address), (topics, data))| {
+            let address = address.as_ref().map(|address| format!("0x{}", hex::encode(address)));
+            decode_log(index, *chain_id, address.as_deref(), topics, data.as_deref().unwrap_or_default())
+        })
+        .collect::<Vec<_>>();
+
+    df.with_column(Series::new("decoded_event", decoded.iter().map(|d| d.as_ref().map(|d| d.name.clone())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_signature", decoded.iter().map(|d| d.as_ref().map(|d| d.signature.clone())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_match", decoded.iter().map(|d| d.as_ref().map(|d| d.source.as_str())).collect::<Vec<_>>()))?;
+    df.with_column(Series::new("decoded_arguments", decoded.iter()
+        .map(|d| d.as_ref().map(|d| serde_json::to_string(&d.arguments)).transpose())
+        .collect::<serde_json::Result<Vec<_>>>()?))?;
+    Ok(decoded.iter().flatten().count())
+}
+
+pub fn decode_logs_file(index: &SignatureIndex, input: &Path, output: &Path, default_chain_id: u64) -> Result<usize> {
+    let mut df = ParquetReader::new(File::open(input)?).finish()?;
+    let decoded = decode_logs(index, &mut df, default_chain_id)?;
+    ParquetWriter::new(File::create(output)?).finish(&mut df)?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0x6b175474e89094c44da98b954eedeac495271d0f";
+
+    /// A row of a combined table: `(contract_address, proxy_address, selector, name, signature, inputs_json)`.
+    type Row<'a> = (&'a str, Option<&'a str>, &'a str, &'a str, &'a str, &'a str);
+
+    /// Writes the rows, all on chain 1, to a parquet table and reads it back as an index.
+    fn read_index(rows: &[Row]) -> SignatureIndex {
+        static TABLES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let table = TABLES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("decode-test-{}-{}.parquet", std::process::id(), table));
+        let mut df = df![
+            "chain_id" => rows.iter().map(|_| 1u64).collect::<Vec<_>>(),
+            "contract_address" => rows.iter().map(|r| r.0).collect::<Vec<_>>(),
+            "proxy_address" => rows.iter().map(|r| r.1).collect::<Vec<_>>(),
+            "selector" => rows.iter().map(|r| r.2).collect::<Vec<_>>(),
+            "name" => rows.iter().map(|r| r.3).collect::<Vec<_>>(),
+            "signature" => rows.iter().map(|r| r.4).collect::<Vec<_>>(),
+            "inputs_json" => rows.iter().map(|r| r.5).collect::<Vec<_>>(),
+        ].unwrap();
+        ParquetWriter::new(File::create(&path).unwrap()).finish(&mut df).unwrap();
+        let index = SignatureIndex::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        index
+    }
+
+    /// An index holding `(selector, name, signature, inputs_json)` entries declared by `TOKEN`.
+    fn index(entries: &[(&str, &str, &str, &str)]) -> SignatureIndex {
+        let rows = entries
+            .iter()
+            .map(|&(selector, name, signature, inputs_json)| (TOKEN, None, selector, name, signature, inputs_json))
+            .collect::<Vec<_>>();
+        read_index(&rows)
+    }
+
+    fn transfer_index() -> SignatureIndex {
+        index(&[(
+            "0xa9059cbb",
+            "transfer",
+            "transfer(address,uint256)",
+            r#"[{"name":"to","type":"address","internalType":"address","components":[]},{"name":"amount","type":"uint256","internalType":"uint256","components":[]}]"#,
+        )])
+    }
+
+    #[test]
+    fn decodes_erc20_transfer_calldata() {
+        let calldata = parse_hex(concat!(
+            "0xa9059cbb",
+            "000000000000000000000000000000000000000000000000000000000000dead",
+            "00000000000000000000000000000000000000000000000000000000000003e8",
+        )).unwrap();
+        let call = decode_calldata(&transfer_index(), 1, Some(TOKEN), &calldata).unwrap();
+        assert_eq!(call.selector, "0xa9059cbb");
+        assert_eq!(call.signature, "transfer(address,uint256)");
+        assert_eq!(call.source, MatchSource::Contract);
+        let arguments = call.arguments.iter().map(|a| (a.name.as_str(), a.ty.as_str(), a.value.clone())).collect::<Vec<_>>();
+        assert_eq!(arguments, vec![
+            ("to", "address", serde_json::json!("0x000000000000000000000000000000000000dead")),
+            ("amount", "uint256", serde_json::json!("1000")),
+        ]);
+
+        // another contract falls back to the global signatures
+        let other = "0x0000000000000000000000000000000000000001";
+        assert_eq!(decode_calldata(&transfer_index(), 1, Some(other), &calldata).unwrap().source, MatchSource::Global);
+    }
+
+    #[test]
+    fn rejects_calldata_that_fits_no_signature() {
+        let index = transfer_index();
+        assert!(decode_calldata(&index, 1, Some(TOKEN), &[0xa9, 0x05]).is_none());
+        // right selector, arguments cut short
+        assert!(decode_calldata(&index, 1, Some(TOKEN), &parse_hex("0xa9059cbb0000").unwrap()).is_none());
+        assert!(decode_calldata(&index, 1, Some(TOKEN), &parse_hex("0x095ea7b3").unwrap()).is_none());
+    }
+
+    const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+    const FROM_TOPIC: &str = "0x000000000000000000000000000000000000000000000000000000000000beef";
+    const TO_TOPIC: &str = "0x000000000000000000000000000000000000000000000000000000000000dead";
+
+    const ERC20_TRANSFER_INPUTS: &str = r#"[{"name":"from","type":"address","indexed":true,"internalType":"address","components":[]},{"name":"to","type":"address","indexed":true,"internalType":"address","components":[]},{"name":"value","type":"uint256","indexed":false,"internalType":"uint256","components":[]}]"#;
+    const ERC721_TRANSFER_INPUTS: &str = r#"[{"name":"from","type":"address","indexed":true,"internalType":"address","components":[]},{"name":"to","type":"address","indexed":true,"internalType":"address","components":[]},{"name":"tokenId","type":"uint256","indexed":true,"internalType":"uint256","components":[]}]"#;
+
+    #[test]
+    fn decodes_erc20_transfer_log() {
+        let index = index(&[(TRANSFER_TOPIC, "Transfer", "Transfer(address,address,uint256)", ERC20_TRANSFER_INPUTS)]);
+        let topics = [TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC].map(|topic| parse_topic(&parse_hex(topic).unwrap()).unwrap());
+        let data = parse_hex("0x00000000000000000000000000000000000000000000000000000000000003e8").unwrap();
+
+        let log = decode_log(&index, 1, Some(TOKEN), &topics, &data).unwrap();
+        assert_eq!(log.topic0, TRANSFER_TOPIC);
+        assert_eq!(log.name, "Transfer");
+        assert_eq!(log.source, MatchSource::Contract);
+        let values = log.arguments.iter().map(|a| (a.name.as_str(), a.value.clone())).collect::<Vec<_>>();
+        assert_eq!(values, vec![
+            ("from", serde_json::json!("0x000000000000000000000000000000000000beef")),
+            ("to", serde_json::json!("0x000000000000000000000000000000000000dead")),
+            ("value", serde_json::json!("1000")),
+        ]);
+
+        // an ERC-721 Transfer shares topic0 but indexes the token id as a fourth topic
+        assert!(decode_log(&index, 1, Some(TOKEN), &[topics[0], topics[1], topics[2], topics[1]], &[]).is_none());
+        assert!(decode_log(&index, 1, Some(TOKEN), &[], &data).is_none());
+    }
+
+    #[test]
+    fn keeps_events_that_index_different_parameters() {
+        let nft = "0xbc4ca0eda7647a8ab7c2061c2e118a18a936f13d";
+        let index = read_index(&[
+            (TOKEN, None, TRANSFER_TOPIC, "Transfer", "Transfer(address,address,uint256)", ERC20_TRANSFER_INPUTS),
+            (nft, None, TRANSFER_TOPIC, "Transfer", "Transfer(address,address,uint256)", ERC721_TRANSFER_INPUTS),
+        ]);
+        let [transfer, from, to] = [TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC].map(|topic| parse_topic(&parse_hex(topic).unwrap()).unwrap());
+        let token_id = B256::with_last_byte(7);
+        let amount = parse_hex("0x00000000000000000000000000000000000000000000000000000000000003e8").unwrap();
+
+        // a contract outside the run falls back to both layouts
+        let other = Some("0x0000000000000000000000000000000000000001");
+        let log = decode_log(&index, 1, other, &[transfer, from, to, token_id], &[]).unwrap();
+        assert_eq!((log.source, log.arguments[2].name.as_str()), (MatchSource::Global, "tokenId"));
+        assert_eq!(log.arguments[2].value, serde_json::json!("7"));
+        let log = decode_log(&index, 1, other, &[transfer, from, to], &amount).unwrap();
+        assert_eq!((log.source, log.arguments[2].name.as_str()), (MatchSource::Global, "value"));
+    }
+
+    #[test]
+    fn reads_an_index_from_a_combined_table() {
+        let (proxy, implementation, other) = (
+            "0x0000000000000000000000000000000000000001",
+            "0x0000000000000000000000000000000000000002",
+            "0x0000000000000000000000000000000000000003",
+        );
+        // two made-up signatures sharing a selector, the second declared more often
+        let inputs = r#"[{"name":"x","type":"uint256","internalType":"uint256","components":[]}]"#;
+        let index = read_index(&[
+            (implementation, Some(proxy), "0x12345678", "rare", "rare(uint256)", inputs),
+            (other, None, "0x12345678", "common", "common(uint256)", inputs),
+            (TOKEN, None, "0x12345678", "common", "common(uint256)", inputs),
+        ]);
+
+        // the implementation's signatures are also the proxy's
+        let signatures = |address: &str| {
+            index.candidates(1, Some(address), "0x12345678")
+                .into_iter()
+                .map(|(candidate, source)| (candidate.signature.as_str(), source))
+                .collect::<Vec<_>>()
+        };
+        for address in [proxy, implementation] {
+            assert_eq!(signatures(address), vec![
+                ("rare(uint256)", MatchSource::Contract),
+                ("common(uint256)", MatchSource::Global),
+                ("rare(uint256)", MatchSource::Global),
+            ]);
+        }
+        // addresses are matched case-insensitively, and duplicate signatures appear once globally
+        assert_eq!(signatures(&format!("0x{}", TOKEN[2..].to_uppercase())).len(), 3);
+        assert_eq!(index.candidates(2, Some(proxy), "0x12345678").len(), 2);
+        assert!(index.candidates(1, Some(proxy), "0x87654321").is_empty());
+    }
+
+    #[test]
+    fn reads_topics_from_separate_columns() {
+        let df = df![
+            "topic0" => [Some(TRANSFER_TOPIC), Some(TRANSFER_TOPIC)],
+            "topic1" => [Some(FROM_TOPIC), None],
+            // ignored after the null topic1
+            "topic2" => [Some(TO_TOPIC), Some(TO_TOPIC)],
+        ].unwrap();
+        let topics = topics_column(&df).unwrap();
+        let hex_topics = topics.iter()
+            .map(|log| log.iter().map(|topic| format!("0x{}", hex::encode(topic))).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(hex_topics, vec![vec![TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC], vec![TRANSFER_TOPIC]]);
+    }
+
+    #[test]
+    fn reads_topics_from_a_list_column() {
+        let df = DataFrame::new(vec![Series::new("topics", &[
+            Series::new("", &[TRANSFER_TOPIC, FROM_TOPIC]),
+            Series::new("", &[TRANSFER_TOPIC]),
+        ])]).unwrap();
+        let topics = topics_column(&df).unwrap();
+        assert_eq!(topics.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(format!("0x{}", hex::encode(topics[0][1])), FROM_TOPIC);
+
+        let short = df!["topic0" => ["0x1234"]].unwrap();
+        assert!(topics_column(&short).is_err());
+        assert!(topics_column(&df!["data" => ["0x"]].unwrap()).is_err());
+    }
+
+    #[test]
+    fn decodes_error_string_revert() {
+        // `revert("Not enough Ether provided.")`, from the Solidity documentation
+        let data = parse_hex(concat!(
+            "0x08c379a0",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "000000000000000000000000000000000000000000000000000000000000001a",
+            "4e6f7420656e6f7567682045746865722070726f76696465642e000000000000",
+        )).unwrap();
+        match decode_revert(None, 1, None, &data) {
+            Some(DecodedRevert::Error { reason }) => assert_eq!(reason, "Not enough Ether provided."),
+            other => panic!("expected an Error(string) revert, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_panic_revert() {
+        let data = parse_hex("0x4e487b710000000000000000000000000000000000000000000000000000000000000011").unwrap();
+        match decode_revert(None, 1, None, &data) {
+            Some(DecodedRevert::Panic { code, reason }) => {
+                assert_eq!(code, "0x11");
+                assert_eq!(reason, "arithmetic overflow or underflow");
+            }
+            other => panic!("expected a Panic(uint256) revert, got {:?}", other),
+        }
+
+        let data = parse_hex("0x4e487b7100000000000000000000000000000000000000000000000000000000000000ff").unwrap();
+        match decode_revert(None, 1, None, &data) {
+            Some(DecodedRevert::Panic { reason, .. }) => assert_eq!(reason, "unknown panic code"),
+            other => panic!("expected a Panic(uint256) revert, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_empty_and_custom_reverts() {
+        assert!(matches!(decode_revert(None, 1, None, &[]), Some(DecodedRevert::Empty)));
+
+        // `InsufficientBalance(uint256,uint256)` is only known through the errors index
+        let data = parse_hex(concat!(
+            "0xcf479181",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        )).unwrap();
+        assert!(decode_revert(None, 1, Some(TOKEN), &data).is_none());
+        let errors = index(&[(
+            "0xcf479181",
+            "InsufficientBalance",
+            "InsufficientBalance(uint256,uint256)",
+            r#"[{"name":"available","type":"uint256","internalType":"uint256","components":[]},{"name":"required","type":"uint256","internalType":"uint256","components":[]}]"#,
+        )]);
+        match decode_revert(Some(&errors), 1, Some(TOKEN), &data) {
+            Some(DecodedRevert::Custom(call)) => assert_eq!(call.signature, "InsufficientBalance(uint256,uint256)"),
+            other => panic!("expected a custom error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn names_documented_panic_codes() {
+        assert_eq!(panic_reason(0x01), Some("assertion failed"));
+        assert_eq!(panic_reason(0x12), Some("division or modulo by zero"));
+        assert_eq!(panic_reason(0x32), Some("array index out of bounds"));
+        assert_eq!(panic_reason(0x02), None);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use alloy_chains::Chain;
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use crate::abi_downloader::create_function_selector;
+use crate::rpc::RpcClient;
+
+// `facets()` from the EIP-2535 DiamondLoupe interface
+const FACETS_SELECTOR: [u8; 4] = [0x7a, 0x0e, 0xd6, 0x27];
+
+#[derive(Debug, Clone)]
+pub struct Facet {
+    pub address: Address,
+    /// Selectors the diamond routes to this facet, as `0x`-prefixed hex.
+    pub selectors: Vec<String>,
+}
+
+/// A contract is treated as a diamond when its ABI exposes the loupe's `facets()`.
+pub fn is_diamond(abi: &JsonAbi) -> bool {
+    let facets_selector = format!("0x{}", hex::encode(FACETS_SELECTOR));
+    abi.functions().any(|f| create_function_selector(f) == facets_selector)
+}
+
+/// Lists the facets of diamonds through each chain's `rpc_url`.
+#[derive(Debug, Clone, Default)]
+pub struct DiamondResolver {
+    rpcs: HashMap<u64, RpcClient>,
+}
+
+impl DiamondResolver {
+    pub fn new(rpcs: HashMap<u64, RpcClient>) -> Self {
+        DiamondResolver { rpcs }
+    }
+
+    pub async fn facets(&self, chain: Chain, diamond: Address) -> Result<Vec<Facet>> {
+        let rpc = self.rpcs.get(&chain.id())
+            .ok_or_else(|| anyhow!("no rpc_url configured for chain {}", chain))?;
+        let output = rpc.call(diamond, &FACETS_SELECTOR).await?;
+        decode_facets(&output)
+    }
+}
+
+fn decode_facets(output: &[u8]) -> Result<Vec<Facet>> {
+    let facets_type = DynSolType::parse("(address,bytes4[])[]")?;
+    let DynSolValue::Array(items) = facets_type.abi_decode(output)? else {
+        return Err(anyhow!("facets() did not return an array"));
+    };
+    items.into_iter()
+        .map(|item| match item {
+            DynSolValue::Tuple(fields) => match fields.as_slice() {
+                [DynSolValue::Address(address), DynSolValue::Array(selectors)] => Ok(Facet {
+                    address: *address,
+                    selectors: selectors.iter()
+                        .filter_map(|selector| match selector {
+                            DynSolValue::FixedBytes(word, 4) => Some(format!("0x{}", hex::encode(&word[..4]))),
+                            _ => None,
+                        })
+                        .collect(),
+                }),
+                _ => Err(anyhow!("unexpected facet tuple from facets()")),
+            },
+            _ => Err(anyhow!("unexpected facet entry from facets()")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn decodes_facets_output() {
+        // one facet routing `transfer(address,uint256)` and `balanceOf(address)`
+        let output = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "a9059cbb00000000000000000000000000000000000000000000000000000000",
+            "70a0823100000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+        let facets = decode_facets(&output).unwrap();
+        assert_eq!(facets.len(), 1);
+        assert_eq!(facets[0].address, address!("1111111111111111111111111111111111111111"));
+        assert_eq!(facets[0].selectors, vec!["0xa9059cbb", "0x70a08231"]);
+    }
+
+    #[test]
+    fn decodes_a_diamond_without_facets() {
+        let output = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+        assert!(decode_facets(&output).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_truncated_facets_output() {
+        assert!(decode_facets(&[]).is_err());
+        assert!(decode_facets(&[0u8; 31]).is_err());
+    }
+}
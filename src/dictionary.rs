@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+
+pub const DICTIONARY_FILE_NAME: &str = "dictionary.parquet";
+
+/// One distinct `(selector, signature, kind)` across every downloaded contract.
+#[derive(Debug, Clone)]
+pub struct DictionaryEntry {
+    pub selector: String,
+    pub signature: String,
+    /// `function`, `event` or `error`.
+    pub kind: String,
+    /// Number of records in the tables with this selector, signature and kind.
+    pub occurrences: u64,
+    pub first_seen_chain_id: u64,
+    pub first_seen_contract: String,
+}
+
+/// Collapses the given `all_*.parquet` tables into a dictionary, in the order
+/// the entries are first seen. Missing tables and anonymous events, which have
+/// no selector, are skipped.
+pub fn build_dictionary(tables: &[PathBuf]) -> Result<Vec<DictionaryEntry>> {
+    let mut entries = Vec::<DictionaryEntry>::new();
+    let mut index = HashMap::new();
+    for table in tables {
+        if !table.exists() {
+            continue;
+        }
+        let df = ParquetReader::new(File::open(table)?)
+            .finish()
+            .map_err(|e| anyhow!("failed to read {}: {}", table.display(), e))?;
+        let kinds = df.column("record_type")?.str()?;
+        let selectors = df.column("selector")?.str()?;
+        let signatures = df.column("signature")?.str()?;
+        let chain_ids = df.column("chain_id")?.u64()?;
+        let addresses = df.column("contract_address")?.str()?;
+
+        for row in 0..df.height() {
+            let (Some(kind), Some(selector), Some(signature), Some(chain_id), Some(address)) = (
+                kinds.get(row),
+                selectors.get(row),
+                signatures.get(row),
+                chain_ids.get(row),
+                addresses.get(row),
+            ) else {
+                continue;
+            };
+            let key = (selector.to_string(), signature.to_string(), kind.to_string());
+            match index.get(&key) {
+                Some(&position) => entries[position].occurrences += 1,
+                None => {
+                    index.insert(key, entries.len());
+                    entries.push(DictionaryEntry {
+                        selector: selector.to_string(),
+                        signature: signature.to_string(),
+                        kind: kind.to_string(),
+                        occurrences: 1,
+                        first_seen_chain_id: chain_id,
+                        first_seen_contract: address.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+pub fn write_dictionary_parquet(entries: &[DictionaryEntry], filename: &Path) -> Result<()> {
+    let mut df = DataFrame::new(vec![
+        Series::new("selector", entries.iter().map(|e| e.selector.clone()).collect::<Vec<_>>()),
+        Series::new("signature", entries.iter().map(|e| e.signature.clone()).collect::<Vec<_>>()),
+        Series::new("kind", entries.iter().map(|e| e.kind.clone()).collect::<Vec<_>>()),
+        Series::new("occurrences", entries.iter().map(|e| e.occurrences).collect::<Vec<_>>()),
+        Series::new("first_seen_chain_id", entries.iter().map(|e| e.first_seen_chain_id).collect::<Vec<_>>()),
+        Series::new("first_seen_contract", entries.iter().map(|e| e.first_seen_contract.clone()).collect::<Vec<_>>()),
+    ])?;
+
+    let file = File::create(filename)?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    Ok(())
+}
+
+/// Writes `{"0xa9059cbb": ["transfer(address,uint256)"], ...}`, the format of
+/// the 4byte.directory signature dumps. Event selectors are full topic hashes,
+/// so they never share a key with functions or errors.
+pub fn write_dictionary_json(entries: &[DictionaryEntry], filename: &Path) -> Result<()> {
+    let mut signatures = BTreeMap::<&str, Vec<&str>>::new();
+    for entry in entries {
+        let selector_signatures = signatures.entry(entry.selector.as_str()).or_default();
+        // an error and a function can share both selector and signature
+        if !selector_signatures.contains(&entry.signature.as_str()) {
+            selector_signatures.push(entry.signature.as_str());
+        }
+    }
+    let file = File::create(filename)?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &signatures)?;
+    Ok(())
+}
+
+/// Writes one `selector signature` line per entry, sorted by selector.
+pub fn write_dictionary_text(entries: &[DictionaryEntry], filename: &Path) -> Result<()> {
+    let mut lines = entries
+        .iter()
+        .map(|e| format!("{} {}", e.selector, e.signature))
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.dedup();
+    let mut writer = BufWriter::new(File::create(filename)?);
+    for line in lines {
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
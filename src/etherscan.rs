@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use alloy_chains::Chain;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use foundry_block_explorers::contract::Metadata;
+use foundry_block_explorers::Client;
+use foundry_block_explorers::errors::EtherscanError;
+use crate::abi_downloader::AddressEntry;
+use crate::config::{ChainConfig, Config};
+use crate::rate_limit::RateLimiter;
+use crate::source::{AbiSource, ContractSource, FetchedAbi};
+
+pub fn create_etherscan_client(chain: Chain, api_key: &str, chain_config: &ChainConfig) -> Result<Client> {
+    let mut builder = Client::builder().with_api_key(api_key);
+    if chain_config.api_url.is_none() || chain_config.browser_url.is_none() {
+        builder = builder.chain(chain)
+            .map_err(|e| anyhow!("No known explorer for chain {}, set api_url and browser_url: {}", chain, e))?;
+    }
+    if let Some(api_url) = &chain_config.api_url {
+        builder = builder.with_api_url(api_url.as_str())
+            .map_err(|e| anyhow!("Invalid api_url '{}': {}", api_url, e))?;
+    }
+    if let Some(browser_url) = &chain_config.browser_url {
+        builder = builder.with_url(browser_url.as_str())
+            .map_err(|e| anyhow!("Invalid browser_url '{}': {}", browser_url, e))?;
+    }
+    builder.build()
+        .map_err(|e| anyhow!("Failed to create Etherscan client: {}", e))
+}
+
+pub struct ChainClient {
+    pub chain: Chain,
+    pub client: Client,
+    pub limiter: Arc<RateLimiter>,
+}
+
+/// Etherscan and Etherscan-compatible explorers, with one client per chain.
+pub struct EtherscanSource {
+    clients: HashMap<u64, ChainClient>,
+}
+
+impl EtherscanSource {
+    pub fn new(config: &Config, entries: &[AddressEntry]) -> Result<Self> {
+        let mut clients = HashMap::new();
+        // the rate limit belongs to the API key, so chains sharing a key share a bucket
+        let mut limiters: HashMap<String, Arc<RateLimiter>> = HashMap::new();
+        for entry in entries {
+            if clients.contains_key(&entry.chain.id()) {
+                continue;
+            }
+            let chain_config = config.chain_config(entry.chain)?;
+            let api_key = chain_config.api_key.clone().ok_or_else(|| anyhow!(
+                "chain {} has no api_key: set one in its [chain.{}] section, or ETHERSCAN_API_KEY under [api_keys]",
+                entry.chain, entry.chain
+            ))?;
+            let client = create_etherscan_client(entry.chain, &api_key, &chain_config)
+                .map_err(|e| anyhow!("chain {}: {}", entry.chain, e))?;
+            let limiter = limiters.entry(api_key)
+                .or_insert_with(|| Arc::new(RateLimiter::new(chain_config.rate_limit)))
+                .clone();
+            clients.insert(entry.chain.id(), ChainClient { chain: entry.chain, client, limiter });
+        }
+        Ok(EtherscanSource { clients })
+    }
+
+    pub fn chain_client(&self, chain: Chain) -> Result<&ChainClient> {
+        self.clients.get(&chain.id())
+            .ok_or_else(|| anyhow!("no Etherscan client configured for chain {}", chain))
+    }
+
+    /// `getsourcecode`, which returns the ABI, the source and the proxy flags in one call.
+    async fn source_metadata(&self, chain: Chain, address: Address) -> Result<Option<Metadata>> {
+        let chain_client = self.chain_client(chain)?;
+        chain_client.limiter.acquire().await;
+        match chain_client.client.contract_source_code(address).await {
+            Ok(metadata) => Ok(metadata.items.into_iter().next()),
+            Err(EtherscanError::ContractCodeNotVerified { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl AbiSource for EtherscanSource {
+    fn name(&self) -> &str {
+        "etherscan"
+    }
+
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>> {
+        Ok(self.fetch_abi_with_source(chain, address).await?.map(|fetched| fetched.abi))
+    }
+
+    async fn fetch_abi_with_source(&self, chain: Chain, address: Address) -> Result<Option<FetchedAbi>> {
+        let Some(item) = self.source_metadata(chain, address).await? else {
+            return Ok(None);
+        };
+        // unverified contracts come back with a message in place of the ABI
+        let Ok(abi) = serde_json::from_str::<JsonAbi>(&item.abi) else {
+            return Ok(None);
+        };
+        Ok(Some(FetchedAbi { abi, source: contract_source(&item) }))
+    }
+
+    async fn fetch_source(&self, chain: Chain, address: Address) -> Result<Option<ContractSource>> {
+        Ok(self.source_metadata(chain, address).await?.as_ref().and_then(contract_source))
+    }
+}
+
+fn contract_source(item: &Metadata) -> Option<ContractSource> {
+    // proxies are flagged in the same response
+    let implementation = item.implementation.filter(|_| item.proxy == 1);
+    if item.source_code().is_empty() && implementation.is_none() {
+        return None;
+    }
+    Some(ContractSource {
+        contract_name: item.contract_name.clone(),
+        compiler_version: item.compiler_version.clone(),
+        optimization_used: item.optimization_used == 1,
+        runs: item.runs,
+        evm_version: item.evm_version.clone(),
+        license: item.license_type.clone(),
+        libraries: item.library.clone(),
+        files: item.sources().into_iter().map(|(path, entry)| (path, entry.content)).collect(),
+        implementation,
+    })
+}
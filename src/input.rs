@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use alloy_chains::Chain;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use crate::abi_downloader::{read_addresses, AddressEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// One `address`, `chain:address` or `chain,address[,label]` per line.
+    Text,
+    Csv,
+    /// An array of objects.
+    Json,
+    /// One object per line.
+    Ndjson,
+    Parquet,
+}
+
+impl InputFormat {
+    /// Picks the format from the file extension, falling back to text.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("csv") => InputFormat::Csv,
+            Some("json") => InputFormat::Json,
+            Some("ndjson") | Some("jsonl") => InputFormat::Ndjson,
+            Some("parquet") => InputFormat::Parquet,
+            _ => InputFormat::Text,
+        }
+    }
+}
+
+/// Columns of a structured address input.
+#[derive(Debug, Clone)]
+pub struct InputColumns {
+    pub address: String,
+    /// Optional, rows without it use the default chain.
+    pub chain: String,
+    /// Optional, becomes the `contract_name` of the contract's records.
+    pub label: String,
+    /// Extra columns copied into every output table as `label_<column>`.
+    pub carry: Vec<String>,
+}
+
+impl Default for InputColumns {
+    fn default() -> Self {
+        InputColumns {
+            address: "address".to_string(),
+            chain: "chain".to_string(),
+            label: "label".to_string(),
+            carry: Vec::new(),
+        }
+    }
+}
+
+/// Column values of a structured input, as strings.
+struct InputTable {
+    columns: HashMap<String, Vec<Option<String>>>,
+    height: usize,
+}
+
+pub fn read_address_input(path: &Path, columns: &InputColumns, default_chain: Chain) -> Result<Vec<AddressEntry>> {
+    let table = match InputFormat::from_path(path) {
+        InputFormat::Text => {
+            if !columns.carry.is_empty() {
+                return Err(anyhow!("label columns require a CSV, JSON, NDJSON or Parquet address file"));
+            }
+            let path = path.to_str().ok_or_else(|| anyhow!("Invalid UTF-8 sequence in address file path"))?;
+            return read_addresses(path, default_chain);
+        }
+        InputFormat::Csv => read_csv_table(path, columns)?,
+        InputFormat::Json => {
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|e| anyhow!("expected an array of objects: {}", e))?;
+            json_table(rows)
+        }
+        InputFormat::Ndjson => read_ndjson_table(path)?,
+        InputFormat::Parquet => dataframe_table(&ParquetReader::new(File::open(path)?).finish()?, columns)?,
+    };
+    table_entries(&table, columns, default_chain)
+}
+
+fn table_entries(table: &InputTable, columns: &InputColumns, default_chain: Chain) -> Result<Vec<AddressEntry>> {
+    let addresses = table.columns.get(&columns.address)
+        .ok_or_else(|| anyhow!("no '{}' address column", columns.address))?;
+    let chains = table.columns.get(&columns.chain);
+    let labels = table.columns.get(&columns.label);
+    let carried = columns.carry
+        .iter()
+        .map(|name| {
+            table.columns.get(name)
+                .map(|values| (name, values))
+                .ok_or_else(|| anyhow!("no '{}' label column", name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut entries = Vec::new();
+    for row in 0..table.height {
+        let Some(address) = addresses[row].as_deref().map(str::trim).filter(|a| !a.is_empty() && !a.starts_with('#')) else {
+            continue;
+        };
+        let chain = match chains.and_then(|chains| chains[row].as_deref()).map(str::trim).filter(|c| !c.is_empty()) {
+            Some(chain) => Chain::from_str(chain)
+                .map_err(|_| anyhow!("row {}: invalid chain '{}'", row + 1, chain))?,
+            None => default_chain,
+        };
+        let address = Address::from_str(address)
+            .map_err(|e| anyhow!("row {}: invalid address '{}': {}", row + 1, address, e))?;
+        entries.push(AddressEntry {
+            chain,
+            address,
+            label: labels.and_then(|labels| labels[row].clone()).filter(|label| !label.is_empty()),
+            labels: carried.iter().map(|(name, values)| (format!("label_{}", name), values[row].clone())).collect(),
+        });
+    }
+    Ok(entries)
+}
+
+fn read_csv_table(path: &Path, columns: &InputColumns) -> Result<InputTable> {
+    // without schema inference every column is read as a string, so addresses
+    // and chain ids are kept as written
+    let df = CsvReadOptions::default()
+        .with_has_header(true)
+        .with_infer_schema_length(Some(0))
+        .map_parse_options(|options| options.with_comment_prefix(Some("#")))
+        .try_into_reader_with_file_path(Some(PathBuf::from(path)))?
+        .finish()?;
+    dataframe_table(&df, columns)
+}
+
+/// Only the columns in `wanted` are converted, so unrelated nested columns,
+/// which can't be cast to strings, don't reject the file.
+fn dataframe_table(df: &DataFrame, wanted: &InputColumns) -> Result<InputTable> {
+    let names = [&wanted.address, &wanted.chain, &wanted.label].into_iter().chain(&wanted.carry);
+    let mut columns = HashMap::new();
+    for name in names {
+        let Ok(column) = df.column(name) else {
+            continue;
+        };
+        let values = column.cast(&DataType::String)
+            .map_err(|e| anyhow!("column '{}' can't be read as text: {}", name, e))?
+            .str()?
+            .into_iter()
+            .map(|value| value.map(String::from))
+            .collect();
+        columns.insert(name.clone(), values);
+    }
+    Ok(InputTable { columns, height: df.height() })
+}
+
+fn read_ndjson_table(path: &Path) -> Result<InputTable> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut rows = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let row = serde_json::from_str(line)
+            .map_err(|e| anyhow!("line {}: expected an object: {}", index + 1, e))?;
+        rows.push(row);
+    }
+    Ok(json_table(rows))
+}
+
+fn json_table(rows: Vec<serde_json::Map<String, serde_json::Value>>) -> InputTable {
+    let mut columns = HashMap::<String, Vec<Option<String>>>::new();
+    for (row, object) in rows.iter().enumerate() {
+        for (name, value) in object {
+            let value = match value {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(s) => Some(s.clone()),
+                value => Some(value.to_string()),
+            };
+            // rows without the key stay null
+            columns.entry(name.clone()).or_insert_with(|| vec![None; rows.len()])[row] = value;
+        }
+    }
+    InputTable { columns, height: rows.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOKEN: &str = "0x6b175474e89094c44da98b954eedeac495271d0f";
+
+    fn rows(values: serde_json::Value) -> Vec<serde_json::Map<String, serde_json::Value>> {
+        serde_json::from_value(values).unwrap()
+    }
+
+    #[test]
+    fn reads_json_rows_into_string_columns() {
+        let table = json_table(rows(json!([
+            {"address": TOKEN, "chain": 137, "verified": true},
+            {"address": TOKEN, "label": null},
+        ])));
+        assert_eq!(table.height, 2);
+        assert_eq!(table.columns["address"], vec![Some(TOKEN.to_string()), Some(TOKEN.to_string())]);
+        // numbers and booleans are kept as written, missing keys and nulls are null
+        assert_eq!(table.columns["chain"], vec![Some("137".to_string()), None]);
+        assert_eq!(table.columns["verified"], vec![Some("true".to_string()), None]);
+        assert_eq!(table.columns["label"], vec![None, None]);
+    }
+
+    #[test]
+    fn builds_entries_from_table_rows() {
+        let table = json_table(rows(json!([
+            {"address": TOKEN, "chain": "polygon", "label": "Dai", "project": "maker"},
+            {"address": "# 0x0000000000000000000000000000000000000001"},
+            {"address": " "},
+            {"chain": 10},
+            {"address": TOKEN, "label": "", "project": null},
+        ])));
+        let columns = InputColumns { carry: vec!["project".to_string()], ..InputColumns::default() };
+        let entries = table_entries(&table, &columns, Chain::mainnet()).unwrap();
+
+        // commented, blank and address-less rows are skipped
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].chain, Chain::from_id(137));
+        assert_eq!(entries[0].address, Address::from_str(TOKEN).unwrap());
+        assert_eq!(entries[0].label.as_deref(), Some("Dai"));
+        assert_eq!(entries[0].labels, vec![("label_project".to_string(), Some("maker".to_string()))]);
+        assert_eq!(entries[1].chain, Chain::mainnet());
+        assert_eq!(entries[1].label, None);
+        assert_eq!(entries[1].labels, vec![("label_project".to_string(), None)]);
+    }
+
+    #[test]
+    fn rejects_missing_columns_and_bad_rows() {
+        let table = json_table(rows(json!([{"address": TOKEN}])));
+        let columns = InputColumns { address: "contract".to_string(), ..InputColumns::default() };
+        assert!(table_entries(&table, &columns, Chain::mainnet()).is_err());
+        let columns = InputColumns { carry: vec!["project".to_string()], ..InputColumns::default() };
+        assert!(table_entries(&table, &columns, Chain::mainnet()).is_err());
+
+        let table = json_table(rows(json!([{"address": "0x1234"}])));
+        assert!(table_entries(&table, &InputColumns::default(), Chain::mainnet()).is_err());
+        let table = json_table(rows(json!([{"address": TOKEN, "chain": "nochain"}])));
+        assert!(table_entries(&table, &InputColumns::default(), Chain::mainnet()).is_err());
+    }
+
+    #[test]
+    fn converts_only_the_wanted_dataframe_columns() {
+        let nested = Series::new("nested", &[Series::new("", &[1i64, 2]), Series::new("", &[3i64])]);
+        let df = DataFrame::new(vec![
+            Series::new("address", &[TOKEN, TOKEN]),
+            Series::new("chain", &[1i64, 10]),
+            nested,
+        ]).unwrap();
+        let table = dataframe_table(&df, &InputColumns::default()).unwrap();
+        assert_eq!(table.columns["chain"], vec![Some("1".to_string()), Some("10".to_string())]);
+        assert!(!table.columns.contains_key("nested"));
+    }
+}
@@ -1 +1,19 @@
-pub mod abi_downloader;
\ No newline at end of file
+pub mod abi_downloader;
+pub mod blockscout;
+pub mod cache;
+pub mod collisions;
+pub mod config;
+pub mod decode;
+pub mod diamond;
+pub mod dictionary;
+pub mod etherscan;
+pub mod input;
+pub mod local;
+pub mod proxy;
+pub mod rate_limit;
+pub mod report;
+pub mod retry;
+pub mod rpc;
+pub mod source;
+pub mod sourcify;
+pub mod state;
\ No newline at end of file
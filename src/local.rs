@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use alloy_chains::Chain;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde_json::Value;
+use crate::abi_downloader::format_address;
+use crate::source::AbiSource;
+
+// build output that never holds a contract ABI and can be very large
+const SKIPPED_DIRS: [&str; 2] = ["build-info", "cache"];
+
+#[derive(Debug, Clone)]
+pub struct LocalAbi {
+    pub path: PathBuf,
+    pub name: String,
+    /// Deployed address, when the file records one (e.g. hardhat-deploy deployments).
+    pub address: Option<Address>,
+    pub abi: JsonAbi,
+}
+
+/// Recursively reads every ABI under `dir`. Accepts bare ABI arrays as well as
+/// Foundry `out/` and Hardhat `artifacts/` files, which hold the ABI under `abi`.
+pub fn read_local_abis(dir: &Path) -> Result<Vec<LocalAbi>> {
+    let mut abis = Vec::new();
+    visit_dir(dir, &mut abis)?;
+    abis.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(abis)
+}
+
+fn visit_dir(dir: &Path, abis: &mut Vec<LocalAbi>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| anyhow!("failed to read ABI dir {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if path.is_dir() {
+            if !SKIPPED_DIRS.contains(&file_name) {
+                visit_dir(&path, abis)?;
+            }
+        } else if file_name.ends_with(".json") && !file_name.ends_with(".dbg.json") {
+            match parse_abi_file(&path) {
+                Ok(Some(abi)) => abis.push(abi),
+                Ok(None) => debug!("Skipping {}, no ABI found", path.display()),
+                Err(e) => warn!("Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_abi_file(path: &Path) -> Result<Option<LocalAbi>> {
+    let contents = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&contents)?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+
+    let (abi_value, name, address) = match value {
+        Value::Array(_) => (value, stem, None),
+        Value::Object(mut artifact) => {
+            let Some(abi_value) = artifact.remove("abi") else {
+                return Ok(None);
+            };
+            let name = artifact.get("contractName")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .unwrap_or(stem);
+            let address = match artifact.get("address").and_then(Value::as_str) {
+                Some(address) => Some(Address::from_str(address)
+                    .map_err(|e| anyhow!("invalid address '{}': {}", address, e))?),
+                None => None,
+            };
+            (abi_value, name, address)
+        }
+        _ => return Ok(None),
+    };
+
+    let abi: JsonAbi = serde_json::from_value(abi_value)
+        .map_err(|e| anyhow!("invalid ABI: {}", e))?;
+    Ok(Some(LocalAbi { path: path.to_path_buf(), name, address, abi }))
+}
+
+/// ABIs in a local directory, found either at `<dir>/<chain_id>/<address>.json`,
+/// at `<dir>/<address>.json`, or in any file under `dir` that records its address.
+pub struct LocalSource {
+    dir: PathBuf,
+    by_address: HashMap<Address, JsonAbi>,
+}
+
+impl LocalSource {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let by_address = read_local_abis(dir)?
+            .into_iter()
+            .filter_map(|local| Some((local.address?, local.abi)))
+            .collect();
+        Ok(LocalSource { dir: dir.to_path_buf(), by_address })
+    }
+}
+
+#[async_trait]
+impl AbiSource for LocalSource {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>> {
+        for file_name in [format!("{}.json", format_address(&address)), format!("{}.json", address.to_checksum(None))] {
+            for path in [self.dir.join(chain.id().to_string()).join(&file_name), self.dir.join(&file_name)] {
+                if path.is_file() {
+                    return Ok(parse_abi_file(&path)?.map(|local| local.abi));
+                }
+            }
+        }
+        Ok(self.by_address.get(&address).cloned())
+    }
+}
@@ -1,24 +1,232 @@
-use log::{error, info, LevelFilter};
+use log::{error, info, warn, LevelFilter};
 use std::process;
 
 use etherscan_abi_downloader::abi_downloader::*;
-use clap::Parser;
-use std::path::PathBuf;
+use etherscan_abi_downloader::cache::AbiCache;
+use etherscan_abi_downloader::collisions::{find_collisions, write_collisions, COLLISIONS_FILE_NAME};
+use etherscan_abi_downloader::blockscout::BlockscoutSource;
+use etherscan_abi_downloader::config::{read_config, Config};
+use etherscan_abi_downloader::decode::{decode_calldata, decode_logs_file, decode_transactions_file, parse_hex, SignatureIndex};
+use etherscan_abi_downloader::diamond::DiamondResolver;
+use etherscan_abi_downloader::dictionary::{build_dictionary, write_dictionary_json, write_dictionary_parquet, write_dictionary_text, DICTIONARY_FILE_NAME};
+use etherscan_abi_downloader::etherscan::EtherscanSource;
+use etherscan_abi_downloader::input::{read_address_input, InputColumns};
+use etherscan_abi_downloader::local::{read_local_abis, LocalSource};
+use etherscan_abi_downloader::source::{AbiSource, FallbackSource};
+use etherscan_abi_downloader::sourcify::{SourcifySource, SOURCIFY_REPOSITORY_URL};
+use etherscan_abi_downloader::proxy::ProxyResolver;
+use etherscan_abi_downloader::retry::RetryPolicy;
+use etherscan_abi_downloader::rpc::rpc_clients;
+use alloy_chains::Chain;
+use alloy_primitives::Address;
+use anyhow::anyhow;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use env_logger::{Builder, Env};
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum SourceKind {
+    Etherscan,
+    Sourcify,
+    Blockscout,
+    Local,
+}
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+// without a subcommand the ABIs are downloaded, as before subcommands existed
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    download: Args,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Find selectors that different signatures share across the downloaded contracts
+    Collisions(CollisionsArgs),
+    /// Export every distinct selector and signature as a 4byte-style dictionary
+    Dictionary(DictionaryArgs),
+    /// Decode calldata with the signatures of a previous run
+    DecodeCalldata(DecodeCalldataArgs),
+    /// Decode event logs with the signatures of a previous run
+    DecodeLogs(DecodeLogsArgs),
+}
+
+#[derive(clap::Args, Debug)]
 struct Args {
-    /// Path to the file containing contract addresses
-    #[clap(short, long, value_parser)]
-    addresses: String,
+    /// Path to the file containing contract addresses: text, or .csv, .json, .ndjson or .parquet with named columns
+    #[clap(short, long, value_parser, required_unless_present = "abi_dir")]
+    addresses: Option<String>,
+
+    /// Column holding the address in a structured address file
+    #[clap(long, value_parser, default_value = "address")]
+    address_column: String,
+
+    /// Column holding the chain name or id in a structured address file
+    #[clap(long, value_parser, default_value = "chain")]
+    chain_column: String,
+
+    /// Column holding the contract name in a structured address file
+    #[clap(long, value_parser, default_value = "label")]
+    label_column: String,
+
+    /// Further columns of a structured address file to copy into every output table as label_<COLUMN>
+    #[clap(long, value_parser, value_delimiter = ',')]
+    label_columns: Vec<String>,
 
     /// Directory to output the parquet files
+    #[clap(short, long, value_parser, required = true)]
+    output_dir: Option<PathBuf>,
+
+    /// Path to the config file, required by the etherscan and blockscout sources
     #[clap(short, long, value_parser)]
-    output_dir: PathBuf,
+    config: Option<PathBuf>,
+
+    /// Where to look up ABIs, in fallback order (e.g. --source etherscan,sourcify)
+    #[clap(long = "source", value_enum, value_delimiter = ',', default_value = "etherscan")]
+    sources: Vec<SourceKind>,
+
+    /// Directory of ABI JSON files and Foundry/Hardhat artifacts. Without --addresses, every ABI in it is processed offline
+    #[clap(long, value_parser)]
+    abi_dir: Option<PathBuf>,
+
+    /// Sourcify repository used by the sourcify source
+    #[clap(long, value_parser, default_value = SOURCIFY_REPOSITORY_URL)]
+    sourcify_url: String,
+
+    /// Maximum number of concurrent downloads
+    #[clap(short, long, value_parser, default_value_t = 4)]
+    workers: usize,
 
-    /// Path to the config file
+    /// Maximum number of attempts per address for rate-limit and network errors
+    #[clap(long, value_parser, default_value_t = 5)]
+    max_attempts: u32,
+
+    /// Only process addresses left outstanding by a previous run in the same output directory
+    #[clap(long, value_parser)]
+    resume: bool,
+
+    /// Directory for caching raw ABIs between runs
+    #[clap(long, value_parser)]
+    cache_dir: Option<PathBuf>,
+
+    /// Ignore cached ABIs older than this many seconds
+    #[clap(long, value_parser, requires = "cache_dir")]
+    max_age: Option<u64>,
+
+    /// Download every ABI again and overwrite the cache
+    #[clap(long, value_parser, requires = "cache_dir")]
+    refresh: bool,
+
+    /// Detect proxies and also download their implementation ABIs, using each chain's rpc_url for storage-slot lookups when set
+    #[clap(long, value_parser)]
+    resolve_proxies: bool,
+
+    /// Detect EIP-2535 diamonds and add the ABIs of their facets, using each chain's rpc_url to call facets()
+    #[clap(long, value_parser)]
+    resolve_diamonds: bool,
+
+    /// Also download verified source code and compiler settings into contracts.parquet and a sources directory (etherscan source only)
+    #[clap(long, value_parser)]
+    with_source: bool,
+
+    /// Chain for addresses without a chain prefix, as a name (e.g. arbitrum) or numeric chain id
+    #[clap(long, value_parser, default_value = "mainnet")]
+    chain: Chain,
+}
+
+#[derive(clap::Args, Debug)]
+struct CollisionsArgs {
+    /// Output directory of a previous run, holding all_functions.parquet and all_errors.parquet
+    #[clap(short, long, value_parser)]
+    input_dir: PathBuf,
+
+    /// Where to write the collision table [default: <INPUT_DIR>/collisions.parquet]
     #[clap(short, long, value_parser)]
-    config: PathBuf,
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DictionaryArgs {
+    /// Output directory of a previous run, holding its all_*.parquet tables
+    #[clap(short, long, value_parser)]
+    input_dir: PathBuf,
+
+    /// Where to write the dictionary table [default: <INPUT_DIR>/dictionary.parquet]
+    #[clap(short, long, value_parser)]
+    output: Option<PathBuf>,
+
+    /// Also write a 4byte.directory-style JSON mapping each selector to its signatures
+    #[clap(long, value_parser)]
+    json: Option<PathBuf>,
+
+    /// Also write a text file with one `selector signature` line per entry
+    #[clap(long, value_parser)]
+    text: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DecodeCalldataArgs {
+    /// Output directory of a previous run, holding all_functions.parquet
+    #[clap(short, long, value_parser)]
+    input_dir: PathBuf,
+
+    /// Hex calldata of a single call
+    #[clap(long, value_parser, required_unless_present = "transactions", conflicts_with = "transactions")]
+    calldata: Option<String>,
+
+    /// Contract the calldata was sent to, whose own ABI is preferred over other contracts'
+    #[clap(long, value_parser, requires = "calldata")]
+    to: Option<Address>,
+
+    /// Parquet file of transactions with `to` and `input` columns, and optionally `chain_id`
+    #[clap(long, value_parser)]
+    transactions: Option<PathBuf>,
+
+    /// Where to write the JSON for --calldata (default stdout) or the parquet for --transactions (default <INPUT_DIR>/decoded_calldata.parquet)
+    #[clap(short, long, value_parser)]
+    output: Option<PathBuf>,
+
+    /// Chain of the calldata, or of transactions without a chain_id column
+    #[clap(long, value_parser, default_value = "mainnet")]
+    chain: Chain,
+}
+
+#[derive(clap::Args, Debug)]
+struct DecodeLogsArgs {
+    /// Output directory of a previous run, holding all_events.parquet
+    #[clap(short, long, value_parser)]
+    input_dir: PathBuf,
+
+    /// Parquet file of logs with `address`, `topics` (or `topic0` to `topic3`) and `data` columns, and optionally `chain_id`
+    #[clap(short, long, value_parser)]
+    logs: PathBuf,
+
+    /// Where to write the decoded logs [default: <INPUT_DIR>/decoded_logs.parquet]
+    #[clap(short, long, value_parser)]
+    output: Option<PathBuf>,
+
+    /// Chain of logs without a chain_id column
+    #[clap(long, value_parser, default_value = "mainnet")]
+    chain: Chain,
+}
+
+fn create_source(kind: SourceKind, args: &Args, config: Option<&Config>, addresses: &[AddressEntry]) -> anyhow::Result<Box<dyn AbiSource>> {
+    let require_config = || config.ok_or_else(|| anyhow!("--config is required"));
+    let source: Box<dyn AbiSource> = match kind {
+        SourceKind::Etherscan => Box::new(EtherscanSource::new(require_config()?, addresses)?),
+        SourceKind::Sourcify => Box::new(SourcifySource::new(&args.sourcify_url)),
+        SourceKind::Blockscout => Box::new(BlockscoutSource::new(require_config()?)?),
+        SourceKind::Local => {
+            let abi_dir = args.abi_dir.as_ref().ok_or_else(|| anyhow!("--abi-dir is required"))?;
+            Box::new(LocalSource::new(abi_dir)?)
+        }
+    };
+    Ok(source)
 }
 
 #[tokio::main]
@@ -27,59 +235,237 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     builder.filter_level(LevelFilter::Info);
     builder.init();
 
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Collisions(args)) => collisions(&args),
+        Some(Command::Dictionary(args)) => dictionary(&args),
+        Some(Command::DecodeCalldata(args)) => decode_calldata_command(&args),
+        Some(Command::DecodeLogs(args)) => decode_logs_command(&args),
+        None => download(&cli.download).await,
+    }
+    Ok(())
+}
+
+async fn download(args: &Args) {
+    // required by clap unless a subcommand is given
+    let output_dir = args.output_dir.as_ref().unwrap();
 
-    let api_key = match args.config.to_str() {
-        Some(config_path) => match read_api_key(config_path) {
-            Ok(key) => key,
+    let table_files = if let (None, Some(abi_dir)) = (&args.addresses, &args.abi_dir) {
+        let abis = match read_local_abis(abi_dir) {
+            Ok(abis) => abis,
             Err(e) => {
-                error!("Failed to read API key from {}: {}", config_path, e);
+                error!("Failed to read ABIs from {}: {}", abi_dir.display(), e);
+                process::exit(1);
+            }
+        };
+
+        match process_local_abis(&abis, args.chain, output_dir) {
+            Ok(abis) => abis,
+            Err(e) => {
+                error!("Failed to process local ABIs: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        // required by clap unless --abi-dir is given
+        let addresses_path = args.addresses.as_deref().unwrap();
+
+        let config = match &args.config {
+            Some(config_path) => match config_path.to_str() {
+                Some(config_path) => match read_config(config_path) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        error!("Failed to read config from {}: {}", config_path, e);
+                        process::exit(1);
+                    }
+                },
+                None => {
+                    error!("Invalid UTF-8 sequence in config path");
+                    process::exit(1);
+                }
+            },
+            None => None,
+        };
+
+        let columns = InputColumns {
+            address: args.address_column.clone(),
+            chain: args.chain_column.clone(),
+            label: args.label_column.clone(),
+            carry: args.label_columns.clone(),
+        };
+        let addresses = match read_address_input(Path::new(addresses_path), &columns, args.chain) {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                error!("Failed to read addresses from {}: {}", addresses_path, e);
+                process::exit(1);
+            }
+        };
+
+        let mut sources = Vec::new();
+        for kind in &args.sources {
+            match create_source(*kind, args, config.as_ref(), &addresses) {
+                Ok(source) => sources.push(source),
+                Err(e) => {
+                    error!("Failed to create {:?} source: {}", kind, e);
+                    process::exit(1);
+                }
+            }
+        }
+        let source = FallbackSource::new(sources);
+
+        let options = DownloadOptions {
+            workers: args.workers,
+            retry: RetryPolicy { max_attempts: args.max_attempts.max(1), ..RetryPolicy::default() },
+            resume: args.resume,
+            cache: args.cache_dir.as_ref()
+                .map(|dir| AbiCache::new(dir, args.max_age.map(Duration::from_secs), args.refresh)),
+            proxies: args.resolve_proxies.then(|| ProxyResolver::new(rpc_clients(config.as_ref()))),
+            diamonds: args.resolve_diamonds.then(|| DiamondResolver::new(rpc_clients(config.as_ref()))),
+            with_source: args.with_source,
+        };
+        match download_abis(&source, &addresses, output_dir, &options).await {
+            Ok(abis) => abis,
+            Err(e) => {
+                error!("Failed to download ABIs: {}", e);
                 process::exit(1);
             }
-        },
-        None => {
-            error!("Invalid UTF-8 sequence in config path");
-            process::exit(1);
         }
     };
 
-    let client = match create_etherscan_client(&api_key) {
-        Ok(client) => client,
+    for (table, files) in table_files.tables() {
+        let all_path = output_dir.join(format!("all_{}.parquet", table));
+        if let Err(e) = concatenate_parquet_files(files, all_path.to_str().unwrap()).await {
+            error!("Failed to concatenate {} files: {}", table, e);
+            process::exit(1);
+        }
+    }
+    if !table_files.contracts.is_empty() {
+        let contracts_path = output_dir.join("contracts.parquet");
+        if let Err(e) = concatenate_parquet_files(&table_files.contracts, contracts_path.to_str().unwrap()).await {
+            error!("Failed to concatenate contracts files: {}", e);
+            process::exit(1);
+        }
+    }
+
+    info!("ABI download and processing completed successfully.");
+}
+
+fn collisions(args: &CollisionsArgs) {
+    let tables = [args.input_dir.join("all_functions.parquet"), args.input_dir.join("all_errors.parquet")];
+    let collisions = match find_collisions(&tables) {
+        Ok(collisions) => collisions,
         Err(e) => {
-            error!("Failed to create Etherscan client: {}", e);
+            error!("Failed to scan {} for collisions: {}", args.input_dir.display(), e);
             process::exit(1);
         }
     };
 
-    let addresses = match read_addresses(&args.addresses) {
-        Ok(addresses) => addresses,
+    let output = args.output.clone().unwrap_or_else(|| args.input_dir.join(COLLISIONS_FILE_NAME));
+    if let Err(e) = write_collisions(&collisions, &output) {
+        error!("Failed to write {}: {}", output.display(), e);
+        process::exit(1);
+    }
+
+    let proxy_clashes = collisions.iter().filter(|c| c.proxy_clash).count();
+    if proxy_clashes > 0 {
+        warn!("{} selectors clash between a proxy and its implementation", proxy_clashes);
+    }
+    info!("Found {} colliding selectors, written to {}", collisions.len(), output.display());
+}
+
+fn dictionary(args: &DictionaryArgs) {
+    let tables = ["functions", "events", "errors"].map(|table| args.input_dir.join(format!("all_{}.parquet", table)));
+    let entries = match build_dictionary(&tables) {
+        Ok(entries) => entries,
         Err(e) => {
-            error!("Failed to read addresses from {}: {}", args.addresses, e);
+            error!("Failed to read signatures from {}: {}", args.input_dir.display(), e);
             process::exit(1);
         }
     };
 
-    let (function_files, event_files) = match download_abis(&client, &addresses, &args.output_dir).await {
-        Ok(abis) => abis,
+    let output = args.output.clone().unwrap_or_else(|| args.input_dir.join(DICTIONARY_FILE_NAME));
+    if let Err(e) = write_dictionary_parquet(&entries, &output) {
+        error!("Failed to write {}: {}", output.display(), e);
+        process::exit(1);
+    }
+    if let Some(json_path) = &args.json {
+        if let Err(e) = write_dictionary_json(&entries, json_path) {
+            error!("Failed to write {}: {}", json_path.display(), e);
+            process::exit(1);
+        }
+    }
+    if let Some(text_path) = &args.text {
+        if let Err(e) = write_dictionary_text(&entries, text_path) {
+            error!("Failed to write {}: {}", text_path.display(), e);
+            process::exit(1);
+        }
+    }
+    info!("Exported {} dictionary entries", entries.len());
+}
+
+fn decode_calldata_command(args: &DecodeCalldataArgs) {
+    let table = args.input_dir.join("all_functions.parquet");
+    let index = match SignatureIndex::read(&table) {
+        Ok(index) => index,
         Err(e) => {
-            error!("Failed to download ABIs: {}", e);
+            error!("Failed to read signatures from {}: {}", table.display(), e);
             process::exit(1);
         }
     };
-    
-    let all_functions_path = args.output_dir.join("all_functions.parquet");
-    let all_events_path = args.output_dir.join("all_events.parquet");
 
-    if let Err(e) = concatenate_parquet_files(&function_files, all_functions_path.to_str().unwrap()).await {
-        error!("Failed to concatenate function files: {}", e);
-        process::exit(1);
+    if let Some(transactions) = &args.transactions {
+        let output = args.output.clone().unwrap_or_else(|| args.input_dir.join("decoded_calldata.parquet"));
+        match decode_transactions_file(&index, transactions, &output, args.chain.id()) {
+            Ok(decoded) => info!("Decoded {} transactions, written to {}", decoded, output.display()),
+            Err(e) => {
+                error!("Failed to decode transactions from {}: {}", transactions.display(), e);
+                process::exit(1);
+            }
+        }
+        return;
     }
 
-    if let Err(e) = concatenate_parquet_files(&event_files, all_events_path.to_str().unwrap()).await {
-        error!("Failed to concatenate event files: {}", e);
+    // required by clap unless --transactions is given
+    let calldata = match parse_hex(args.calldata.as_deref().unwrap()) {
+        Ok(calldata) => calldata,
+        Err(e) => {
+            error!("Invalid calldata: {}", e);
+            process::exit(1);
+        }
+    };
+    let to = args.to.as_ref().map(format_address);
+    let Some(decoded) = decode_calldata(&index, args.chain.id(), to.as_deref(), &calldata) else {
+        error!("No signature in {} matches the calldata", table.display());
         process::exit(1);
+    };
+    let json = serde_json::to_string_pretty(&decoded).unwrap();
+    match &args.output {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, json) {
+                error!("Failed to write {}: {}", output.display(), e);
+                process::exit(1);
+            }
+        }
+        None => println!("{}", json),
     }
+}
 
-    info!("ABI download and processing completed successfully.");
-    Ok(())
+fn decode_logs_command(args: &DecodeLogsArgs) {
+    let table = args.input_dir.join("all_events.parquet");
+    let index = match SignatureIndex::read(&table) {
+        Ok(index) => index,
+        Err(e) => {
+            error!("Failed to read signatures from {}: {}", table.display(), e);
+            process::exit(1);
+        }
+    };
+
+    let output = args.output.clone().unwrap_or_else(|| args.input_dir.join("decoded_logs.parquet"));
+    match decode_logs_file(&index, &args.logs, &output, args.chain.id()) {
+        Ok(decoded) => info!("Decoded {} logs, written to {}", decoded, output.display()),
+        Err(e) => {
+            error!("Failed to decode logs from {}: {}", args.logs.display(), e);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use alloy_chains::Chain;
+use alloy_primitives::{keccak256, Address, B256, U256};
+use anyhow::Result;
+use crate::rpc::RpcClient;
+
+// `implementation()` on an EIP-1967 beacon
+const BEACON_IMPLEMENTATION_SELECTOR: [u8; 4] = [0x5c, 0x60, 0xda, 0x1b];
+
+/// EIP-1967 slots are the hash of their label minus one.
+fn eip1967_slot(label: &str) -> B256 {
+    let slot = U256::from_be_bytes(keccak256(label).0) - U256::from(1);
+    B256::new(slot.to_be_bytes::<32>())
+}
+
+fn word_to_address(word: &[u8]) -> Option<Address> {
+    if word.len() < 32 {
+        return None;
+    }
+    let address = Address::from_slice(&word[12..32]);
+    (address != Address::ZERO).then_some(address)
+}
+
+/// Finds the implementation behind proxy contracts, first from the source's
+/// metadata and then, for chains with an `rpc_url`, from the proxy's storage.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyResolver {
+    rpcs: HashMap<u64, RpcClient>,
+}
+
+impl ProxyResolver {
+    pub fn new(rpcs: HashMap<u64, RpcClient>) -> Self {
+        ProxyResolver { rpcs }
+    }
+
+    pub fn rpc(&self, chain: Chain) -> Option<&RpcClient> {
+        self.rpcs.get(&chain.id())
+    }
+
+    /// `recorded` is the implementation from the proxy's source metadata, if any.
+    pub async fn resolve(&self, chain: Chain, address: Address, recorded: Option<Address>) -> Result<Option<Address>> {
+        if recorded.is_some() {
+            return Ok(recorded);
+        }
+        match self.rpc(chain) {
+            Some(rpc) => storage_implementation(rpc, address).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// Reads the EIP-1967 implementation slot, the EIP-1822 `PROXIABLE` slot and
+/// finally the EIP-1967 beacon slot, asking the beacon for its implementation.
+pub async fn storage_implementation(rpc: &RpcClient, address: Address) -> Result<Option<Address>> {
+    for slot in [eip1967_slot("eip1967.proxy.implementation"), keccak256("PROXIABLE")] {
+        let word = rpc.get_storage_at(address, slot).await?;
+        if let Some(implementation) = word_to_address(word.as_slice()) {
+            return Ok(Some(implementation));
+        }
+    }
+
+    let beacon_word = rpc.get_storage_at(address, eip1967_slot("eip1967.proxy.beacon")).await?;
+    match word_to_address(beacon_word.as_slice()) {
+        Some(beacon) => {
+            let output = rpc.call(beacon, &BEACON_IMPLEMENTATION_SELECTOR).await?;
+            Ok(word_to_address(&output))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256};
+
+    #[test]
+    fn derives_eip1967_slots() {
+        assert_eq!(
+            eip1967_slot("eip1967.proxy.implementation"),
+            b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc"),
+        );
+        assert_eq!(
+            eip1967_slot("eip1967.proxy.beacon"),
+            b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50"),
+        );
+        assert_eq!(
+            keccak256("PROXIABLE"),
+            b256!("c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7"),
+        );
+    }
+
+    #[test]
+    fn reads_addresses_from_storage_words() {
+        let word = b256!("000000000000000000000000d9db270c1b5e3bd161e8c8503c55ceabee709552");
+        assert_eq!(word_to_address(word.as_slice()), Some(address!("d9db270c1b5e3bd161e8c8503c55ceabee709552")));
+        assert_eq!(word_to_address(B256::ZERO.as_slice()), None);
+        assert_eq!(word_to_address(&word[..20]), None);
+    }
+
+    #[tokio::test]
+    async fn prefers_the_recorded_implementation() {
+        let recorded = address!("d9db270c1b5e3bd161e8c8503c55ceabee709552");
+        let resolver = ProxyResolver::default();
+        let proxy = address!("0000000000000000000000000000000000000001");
+        assert_eq!(resolver.resolve(Chain::mainnet(), proxy, Some(recorded)).await.unwrap(), Some(recorded));
+        // without a recorded implementation or an rpc_url there is nothing to read
+        assert_eq!(resolver.resolve(Chain::mainnet(), proxy, None).await.unwrap(), None);
+    }
+}
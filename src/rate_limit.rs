@@ -0,0 +1,47 @@
+use tokio::sync::Mutex;
+use tokio::time::{self, Duration, Instant};
+
+/// Token bucket shared by every worker that uses the same API key. It holds at
+/// most one token, so calls are spaced `1 / calls_per_second` apart and no burst,
+/// even after an idle period, can exceed the rate within a second.
+pub struct RateLimiter {
+    calls_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(calls_per_second: f64) -> Self {
+        RateLimiter {
+            calls_per_second,
+            bucket: Mutex::new(Bucket { tokens: 1.0, last_refill: Instant::now() }),
+        }
+    }
+
+    pub fn calls_per_second(&self) -> f64 {
+        self.calls_per_second
+    }
+
+    /// Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.calls_per_second).min(1.0);
+                bucket.last_refill = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.calls_per_second)
+            };
+            time::sleep(wait).await;
+        }
+    }
+}
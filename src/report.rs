@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::abi_downloader::ContractRecords;
+
+pub const REPORT_FILE_NAME: &str = "run_report.json";
+
+/// Records of one type in a single ABI that share a selector. Solidity never
+/// compiles such a contract, so this means the ABI is broken or was edited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorViolation {
+    pub chain_id: u64,
+    pub contract_address: String,
+    pub record_type: String,
+    pub selector: String,
+    pub signatures: Vec<String>,
+}
+
+/// Summary of a run, written to `run_report.json` in the output directory.
+#[derive(Debug, Default, Serialize)]
+pub struct RunReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub pending: usize,
+    pub selector_violations: Vec<SelectorViolation>,
+}
+
+impl RunReport {
+    pub fn write(&self, output_dir: &Path) -> Result<()> {
+        let path = output_dir.join(REPORT_FILE_NAME);
+        let file = File::create(&path)
+            .map_err(|e| anyhow!("failed to create report {}: {}", path.display(), e))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+pub fn selector_violations(records: &ContractRecords) -> Vec<SelectorViolation> {
+    let mut by_selector = BTreeMap::<_, Vec<_>>::new();
+    for record in records.functions.iter().chain(&records.events).chain(&records.errors) {
+        let Some(selector) = &record.selector else {
+            continue;
+        };
+        by_selector
+            .entry((record.chain_id, record.contract_address.as_str(), record.record_type.as_str(), selector.as_str()))
+            .or_default()
+            .push(record.signature.clone());
+    }
+    by_selector
+        .into_iter()
+        .filter(|(_, signatures)| signatures.len() > 1)
+        .map(|((chain_id, contract_address, record_type, selector), signatures)| SelectorViolation {
+            chain_id,
+            contract_address: contract_address.to_string(),
+            record_type: record_type.to_string(),
+            selector: selector.to_string(),
+            signatures,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi_downloader::AbiRecord;
+
+    fn record(record_type: &str, address: &str, selector: &str, signature: &str) -> AbiRecord {
+        AbiRecord {
+            chain_id: 1,
+            record_type: record_type.to_string(),
+            contract_address: address.to_string(),
+            signature: signature.to_string(),
+            selector: Some(selector.to_string()),
+            ..AbiRecord::default()
+        }
+    }
+
+    #[test]
+    fn reports_selectors_shared_within_one_abi() {
+        let token = "0x6b175474e89094c44da98b954eedeac495271d0f";
+        let records = ContractRecords {
+            functions: vec![
+                record("function", token, "0xa9059cbb", "transfer(address,uint256)"),
+                // the same entry listed twice
+                record("function", token, "0xa9059cbb", "transfer(address,uint256)"),
+                record("function", token, "0x095ea7b3", "approve(address,uint256)"),
+                // another contract's records are checked on their own
+                record("function", "0x0000000000000000000000000000000000000001", "0x095ea7b3", "approve(address,uint256)"),
+            ],
+            // an error may share a function's selector
+            errors: vec![record("error", token, "0xa9059cbb", "transfer(address,uint256)")],
+            ..ContractRecords::default()
+        };
+        assert_eq!(selector_violations(&records), vec![SelectorViolation {
+            chain_id: 1,
+            contract_address: token.to_string(),
+            record_type: "function".to_string(),
+            selector: "0xa9059cbb".to_string(),
+            signatures: vec!["transfer(address,uint256)".to_string(); 2],
+        }]);
+
+        let mut events = ContractRecords::default();
+        events.events.push(record("event", token, "0x00", "A()"));
+        assert!(selector_violations(&events).is_empty());
+    }
+}
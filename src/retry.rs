@@ -0,0 +1,234 @@
+use std::fmt;
+use std::future::Future;
+use foundry_block_explorers::errors::EtherscanError;
+use log::warn;
+use rand::Rng;
+use reqwest::StatusCode;
+use tokio::time::{self, Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    RateLimited,
+    NotVerified,
+    InvalidApiKey,
+    Network,
+    MalformedResponse,
+    Other,
+}
+
+impl ErrorClass {
+    /// Transient failures are worth retrying after a backoff.
+    pub fn is_transient(self) -> bool {
+        matches!(self, ErrorClass::RateLimited | ErrorClass::Network)
+    }
+
+    /// Fatal failures will fail for every address, so the run should stop.
+    pub fn is_fatal(self) -> bool {
+        matches!(self, ErrorClass::InvalidApiKey)
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ErrorClass::RateLimited => "rate limited",
+            ErrorClass::NotVerified => "contract not verified",
+            ErrorClass::InvalidApiKey => "invalid API key",
+            ErrorClass::Network => "network error",
+            ErrorClass::MalformedResponse => "malformed response",
+            ErrorClass::Other => "error",
+        };
+        f.write_str(s)
+    }
+}
+
+pub fn classify(error: &anyhow::Error) -> ErrorClass {
+    if let Some(e) = error.downcast_ref::<EtherscanError>() {
+        classify_etherscan(e)
+    } else if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        classify_http(e)
+    } else if error.downcast_ref::<serde_json::Error>().is_some() {
+        ErrorClass::MalformedResponse
+    } else {
+        classify_message(&error.to_string())
+    }
+}
+
+fn classify_etherscan(error: &EtherscanError) -> ErrorClass {
+    match error {
+        EtherscanError::RateLimitExceeded { .. }
+        | EtherscanError::BlockedByCloudflare { .. }
+        | EtherscanError::CloudFlareSecurityChallenge { .. } => ErrorClass::RateLimited,
+        EtherscanError::ContractCodeNotVerified { .. } => ErrorClass::NotVerified,
+        EtherscanError::InvalidApiKey { .. } => ErrorClass::InvalidApiKey,
+        EtherscanError::Reqwest { .. } => ErrorClass::Network,
+        EtherscanError::Serde { .. } => ErrorClass::MalformedResponse,
+        _ => {
+            let message = error.to_string();
+            let lowercase = message.to_lowercase();
+            if lowercase.contains("invalid api key") || lowercase.contains("missing/invalid api key") {
+                ErrorClass::InvalidApiKey
+            } else {
+                classify_message(&message)
+            }
+        }
+    }
+}
+
+fn classify_http(error: &reqwest::Error) -> ErrorClass {
+    match error.status() {
+        // a 403 from other backends is usually a WAF or bot challenge, not a bad key,
+        // and only Etherscan keys are checked, so neither status stops the run
+        Some(StatusCode::TOO_MANY_REQUESTS) | Some(StatusCode::FORBIDDEN) => ErrorClass::RateLimited,
+        Some(StatusCode::UNAUTHORIZED) => ErrorClass::Other,
+        Some(status) if status.is_server_error() => ErrorClass::Network,
+        Some(_) => ErrorClass::Other,
+        None if error.is_decode() => ErrorClass::MalformedResponse,
+        None => ErrorClass::Network,
+    }
+}
+
+// generic error responses only carry the backend's message; an API key
+// complaint is only fatal when it comes from Etherscan, see `classify_etherscan`
+fn classify_message(message: &str) -> ErrorClass {
+    let message = message.to_lowercase();
+    if message.contains("rate limit") {
+        ErrorClass::RateLimited
+    } else if message.contains("not verified") {
+        ErrorClass::NotVerified
+    } else if message.contains("timed out") || message.contains("timeout") {
+        ErrorClass::Network
+    } else {
+        ErrorClass::Other
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter in `[delay / 2, delay)`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay = self.base_delay.saturating_mul(1 << exponent).min(self.max_delay);
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..1.0))
+    }
+}
+
+#[derive(Debug)]
+pub struct RetryError {
+    pub class: ErrorClass,
+    pub attempts: u32,
+    pub source: anyhow::Error,
+}
+
+impl fmt::Display for RetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} after {} attempt(s): {}", self.class, self.attempts, self.source)
+    }
+}
+
+impl std::error::Error for RetryError {}
+
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, label: &str, mut operation: F) -> Result<T, RetryError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let class = classify(&e);
+                if !class.is_transient() || attempt >= policy.max_attempts {
+                    return Err(RetryError { class, attempts: attempt, source: e });
+                }
+                let delay = policy.backoff(attempt);
+                warn!("{}: {} ({}), retrying in {:?} (attempt {}/{})", label, class, e, delay, attempt + 1, policy.max_attempts);
+                time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// The error reqwest returns for a response with `status`, served once from localhost.
+    async fn http_error(status: u16) -> reqwest::Error {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let response = format!("HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        reqwest::get(url).await.unwrap().error_for_status().unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn classifies_http_statuses() {
+        assert_eq!(classify_http(&http_error(429).await), ErrorClass::RateLimited);
+        // neither is fatal, only Etherscan's own key error stops the run
+        assert_eq!(classify_http(&http_error(403).await), ErrorClass::RateLimited);
+        assert_eq!(classify_http(&http_error(401).await), ErrorClass::Other);
+        assert_eq!(classify_http(&http_error(502).await), ErrorClass::Network);
+        assert_eq!(classify_http(&http_error(503).await), ErrorClass::Network);
+        assert_eq!(classify_http(&http_error(404).await), ErrorClass::Other);
+        assert_eq!(classify(&http_error(500).await.into()), ErrorClass::Network);
+    }
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(classify(&EtherscanError::InvalidApiKey.into()), ErrorClass::InvalidApiKey);
+        assert_eq!(classify(&EtherscanError::RateLimitExceeded.into()), ErrorClass::RateLimited);
+        assert_eq!(classify(&serde_json::from_str::<u8>("x").unwrap_err().into()), ErrorClass::MalformedResponse);
+        assert_eq!(classify(&anyhow::anyhow!("Max rate limit reached")), ErrorClass::RateLimited);
+        assert_eq!(classify(&anyhow::anyhow!("Contract source code not verified")), ErrorClass::NotVerified);
+        assert_eq!(classify(&anyhow::anyhow!("operation timed out")), ErrorClass::Network);
+        // a key complaint from another backend is not fatal
+        assert_eq!(classify(&anyhow::anyhow!("Invalid API Key")), ErrorClass::Other);
+
+        assert!(ErrorClass::RateLimited.is_transient() && ErrorClass::Network.is_transient());
+        assert!(!ErrorClass::NotVerified.is_transient() && !ErrorClass::InvalidApiKey.is_transient());
+        assert!(ErrorClass::InvalidApiKey.is_fatal() && !ErrorClass::RateLimited.is_fatal());
+    }
+
+    #[test]
+    fn bounds_backoff_delays() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            // powers of two keep the jittered delays exact in floating point
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(64),
+        };
+        for attempt in 1..=40 {
+            let delay = (policy.base_delay * 2u32.pow((attempt - 1).min(16))).min(policy.max_delay);
+            let backoff = policy.backoff(attempt);
+            assert!(backoff >= delay / 2 && backoff < delay, "attempt {}: {:?} outside [{:?}, {:?})", attempt, backoff, delay / 2, delay);
+        }
+        assert!(policy.backoff(u32::MAX) < policy.max_delay);
+        assert!(policy.backoff(0) < policy.base_delay);
+    }
+}
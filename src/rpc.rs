@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use alloy_primitives::{Address, B256};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use crate::abi_downloader::format_address;
+use crate::config::Config;
+
+/// Minimal JSON-RPC client for the few node calls the downloader needs.
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        RpcClient { url: url.to_string(), http: reqwest::Client::new() }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = self.http.post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error));
+        }
+        response.get_mut("result")
+            .map(Value::take)
+            .ok_or_else(|| anyhow!("{} returned no result", method))
+    }
+
+    pub async fn get_storage_at(&self, address: Address, slot: B256) -> Result<B256> {
+        let params = json!([format_address(&address), format!("0x{}", hex::encode(slot.as_slice())), "latest"]);
+        let result = self.request("eth_getStorageAt", params).await?;
+        let word = result.as_str().ok_or_else(|| anyhow!("eth_getStorageAt returned {}", result))?;
+        B256::from_str(word).map_err(|e| anyhow!("invalid storage word '{}': {}", word, e))
+    }
+
+    pub async fn call(&self, to: Address, data: &[u8]) -> Result<Vec<u8>> {
+        let params = json!([{ "to": format_address(&to), "data": format!("0x{}", hex::encode(data)) }, "latest"]);
+        let result = self.request("eth_call", params).await?;
+        let output = result.as_str().ok_or_else(|| anyhow!("eth_call returned {}", result))?;
+        hex::decode(output.trim_start_matches("0x")).map_err(|e| anyhow!("invalid eth_call output: {}", e))
+    }
+}
+
+/// One client per chain whose config section sets `rpc_url`.
+pub fn rpc_clients(config: Option<&Config>) -> HashMap<u64, RpcClient> {
+    config
+        .map(|config| config.chains.iter()
+            .filter_map(|(chain_id, chain_config)| {
+                Some((*chain_id, RpcClient::new(chain_config.rpc_url.as_deref()?)))
+            })
+            .collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use alloy_chains::Chain;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::Result;
+use async_trait::async_trait;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use crate::retry::classify;
+
+/// Verified source code of a contract and the settings it was compiled with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContractSource {
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub optimization_used: bool,
+    pub runs: u64,
+    pub evm_version: String,
+    pub license: String,
+    /// Linked libraries as reported by the explorer, e.g. `Lib:0x...`.
+    pub libraries: String,
+    /// Contents keyed by the path of each file in the compilation.
+    pub files: BTreeMap<String, String>,
+    /// Implementation contract, when the explorer has marked this one as a proxy.
+    pub implementation: Option<Address>,
+}
+
+/// An ABI and, when the same lookup returned it, the contract's source metadata.
+#[derive(Debug, Clone)]
+pub struct FetchedAbi {
+    pub abi: JsonAbi,
+    pub source: Option<ContractSource>,
+}
+
+/// Somewhere verified contract ABIs can be looked up.
+#[async_trait]
+pub trait AbiSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Returns `Ok(None)` when the source has no ABI for the contract, so that
+    /// callers can fall back to another source.
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>>;
+
+    /// Like `fetch_abi`, along with the source metadata for sources whose ABI
+    /// lookup returns it too, so that names and proxies need no extra request.
+    async fn fetch_abi_with_source(&self, chain: Chain, address: Address) -> Result<Option<FetchedAbi>> {
+        Ok(self.fetch_abi(chain, address).await?.map(|abi| FetchedAbi { abi, source: None }))
+    }
+
+    /// Source code, compiler settings and proxy implementation, for sources
+    /// that serve them.
+    async fn fetch_source(&self, _chain: Chain, _address: Address) -> Result<Option<ContractSource>> {
+        Ok(None)
+    }
+}
+
+/// Tries each source in order and returns the first ABI found.
+pub struct FallbackSource {
+    name: String,
+    sources: Vec<Box<dyn AbiSource>>,
+}
+
+impl FallbackSource {
+    pub fn new(sources: Vec<Box<dyn AbiSource>>) -> Self {
+        let name = sources.iter().map(|s| s.name()).collect::<Vec<_>>().join(", ");
+        FallbackSource { name, sources }
+    }
+}
+
+#[async_trait]
+impl AbiSource for FallbackSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>> {
+        Ok(self.fetch_abi_with_source(chain, address).await?.map(|fetched| fetched.abi))
+    }
+
+    async fn fetch_abi_with_source(&self, chain: Chain, address: Address) -> Result<Option<FetchedAbi>> {
+        let mut first_error = None;
+        for source in &self.sources {
+            match source.fetch_abi_with_source(chain, address).await {
+                Ok(Some(fetched)) => return Ok(Some(fetched)),
+                Ok(None) => debug!("{} has no ABI for {} on {}", source.name(), address, chain),
+                Err(e) if classify(&e).is_fatal() => return Err(e),
+                Err(e) => {
+                    debug!("{} failed for {} on {}: {}", source.name(), address, chain, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        // a failed source might still have the ABI, so report the failure rather than a miss
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    async fn fetch_source(&self, chain: Chain, address: Address) -> Result<Option<ContractSource>> {
+        let mut first_error = None;
+        for source in &self.sources {
+            match source.fetch_source(chain, address).await {
+                Ok(Some(contract_source)) => return Ok(Some(contract_source)),
+                Ok(None) => {}
+                Err(e) if classify(&e).is_fatal() => return Err(e),
+                Err(e) => {
+                    debug!("{} failed to fetch the source of {} on {}: {}", source.name(), address, chain, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use alloy_chains::Chain;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Address;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_json::Value;
+use crate::source::AbiSource;
+
+pub const SOURCIFY_REPOSITORY_URL: &str = "https://repo.sourcify.dev";
+
+/// Reads ABIs from a Sourcify repository, which serves the compiler metadata of
+/// verified contracts under `contracts/{full_match,partial_match}/<chain_id>/<address>/`.
+pub struct SourcifySource {
+    repository_url: String,
+    http: reqwest::Client,
+}
+
+impl SourcifySource {
+    pub fn new(repository_url: &str) -> Self {
+        SourcifySource {
+            repository_url: repository_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn fetch_metadata(&self, match_type: &str, chain: Chain, address: Address) -> Result<Option<Value>> {
+        let url = format!(
+            "{}/contracts/{}/{}/{}/metadata.json",
+            self.repository_url, match_type, chain.id(), address.to_checksum(None)
+        );
+        let response = self.http.get(&url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json::<Value>().await?))
+    }
+}
+
+impl Default for SourcifySource {
+    fn default() -> Self {
+        SourcifySource::new(SOURCIFY_REPOSITORY_URL)
+    }
+}
+
+#[async_trait]
+impl AbiSource for SourcifySource {
+    fn name(&self) -> &str {
+        "sourcify"
+    }
+
+    async fn fetch_abi(&self, chain: Chain, address: Address) -> Result<Option<JsonAbi>> {
+        for match_type in ["full_match", "partial_match"] {
+            let Some(mut metadata) = self.fetch_metadata(match_type, chain, address).await? else {
+                continue;
+            };
+            let abi = metadata.pointer_mut("/output/abi")
+                .map(Value::take)
+                .ok_or_else(|| anyhow!("Sourcify metadata for {} has no output.abi", address))?;
+            return Ok(Some(serde_json::from_value(abi)?));
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::abi_downloader::ContractFiles;
+use crate::report::SelectorViolation;
+
+pub const STATE_FILE_NAME: &str = "run_state.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateEntry {
+    pub chain_id: u64,
+    pub address: String,
+    pub status: EntryStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<ContractFiles>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<SelectorViolation>,
+}
+
+impl StateEntry {
+    pub fn pending(chain_id: u64, address: &str) -> Self {
+        StateEntry {
+            chain_id,
+            address: address.to_string(),
+            status: EntryStatus::Pending,
+            error: None,
+            files: None,
+            violations: Vec::new(),
+        }
+    }
+
+    /// Succeeded entries whose output files were removed are downloaded again.
+    pub fn is_done(&self) -> bool {
+        match self.status {
+            EntryStatus::Succeeded => self.files.as_ref().is_some_and(|files| files.exist()),
+            EntryStatus::Failed => true,
+            EntryStatus::Pending => false,
+        }
+    }
+}
+
+/// Progress of a run, persisted as an append-only log in the output directory.
+/// Each line is a [`StateEntry`] and later lines override earlier ones, so a
+/// crash can lose at most the line being written.
+pub struct RunState {
+    entries: Mutex<HashMap<(u64, String), StateEntry>>,
+    file: Mutex<File>,
+}
+
+impl RunState {
+    /// Loads the previous state when `resume` is set, otherwise starts a fresh log.
+    pub fn open(output_dir: &Path, resume: bool) -> Result<Self> {
+        let path = output_dir.join(STATE_FILE_NAME);
+        let mut entries = HashMap::new();
+        if resume && path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            for (index, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: StateEntry = serde_json::from_str(&line)
+                    .map_err(|e| anyhow!("{}:{}: invalid state entry: {}", path.display(), index + 1, e))?;
+                entries.insert((entry.chain_id, entry.address.clone()), entry);
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume)
+            .truncate(!resume)
+            .open(&path)
+            .map_err(|e| anyhow!("failed to open state file {}: {}", path.display(), e))?;
+
+        Ok(RunState { entries: Mutex::new(entries), file: Mutex::new(file) })
+    }
+
+    pub fn get(&self, chain_id: u64, address: &str) -> Option<StateEntry> {
+        self.entries.lock().unwrap().get(&(chain_id, address.to_string())).cloned()
+    }
+
+    pub fn is_done(&self, chain_id: u64, address: &str) -> bool {
+        self.get(chain_id, address).is_some_and(|entry| entry.is_done())
+    }
+
+    pub fn record(&self, entry: StateEntry) -> Result<()> {
+        let line = serde_json::to_string(&entry)?;
+        {
+            let mut file = self.file.lock().unwrap();
+            writeln!(file, "{}", line)?;
+            file.flush()?;
+        }
+        self.entries.lock().unwrap().insert((entry.chain_id, entry.address.clone()), entry);
+        Ok(())
+    }
+}